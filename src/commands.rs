@@ -1,5 +1,16 @@
+pub mod agenda;
+pub mod backup;
+pub mod board;
 pub mod diary;
+pub mod export;
+pub mod graph;
+pub mod import;
+pub mod meetings;
 pub mod project;
+pub mod publish;
+pub mod query;
 pub mod quick_note;
+pub mod sql;
 pub mod sync;
+pub mod tasks;
 
@@ -0,0 +1,152 @@
+use crate::core::config::Config;
+use crate::core::notes::{self, Notebook};
+use crate::core::tasks::{self, Task};
+use crate::core::utils;
+use chrono::{Datelike, Days, Local, NaiveDate, Weekday};
+use std::io;
+
+struct Entry {
+    day: NaiveDate,
+    text: String,
+    path: Option<String>,
+    line: usize,
+}
+
+fn recurs_on(task: &Task, day: NaiveDate) -> bool {
+    let Some(rule) = task.annotations.get("rec") else {
+        return false;
+    };
+    if let Ok(weekday) = rule.parse::<Weekday>() {
+        return day.weekday() == weekday;
+    }
+    let Some(anchor) = task.due else {
+        return rule == "daily";
+    };
+    if day < anchor {
+        return false;
+    }
+    match rule.as_str() {
+        "daily" => true,
+        "weekly" => day.weekday() == anchor.weekday(),
+        "monthly" => day.day() == anchor.day(),
+        "yearly" => day.day() == anchor.day() && day.month() == anchor.month(),
+        _ => false,
+    }
+}
+
+fn task_entry(day: NaiveDate, task: &Task, prefix: &str) -> Entry {
+    let mut text = format!("{}[ ] ", prefix);
+    if let Some(priority) = task.priority {
+        text.push_str(&format!("({}) ", priority));
+    }
+    text.push_str(&task.description);
+    Entry {
+        day,
+        text,
+        path: Some(task.path.to_string_lossy().to_string()),
+        line: task.line,
+    }
+}
+
+fn build(days: &[NaiveDate], config: &Config) -> Result<Vec<Entry>, io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let open: Vec<Task> = tasks::collect(&notebook)
+        .into_iter()
+        .filter(|t| !t.done)
+        .collect();
+    let today = Local::now().date_naive();
+
+    let mut entries = Vec::new();
+    for day in days {
+        let diary = notes::daily_diary_path(&config.core.note_dir, *day);
+        if utils::path_exists(&diary) {
+            entries.push(Entry {
+                day: *day,
+                text: "diary entry".to_string(),
+                path: Some(diary),
+                line: 1,
+            });
+        } else {
+            entries.push(Entry {
+                day: *day,
+                text: "no diary entry".to_string(),
+                path: None,
+                line: 0,
+            });
+        }
+
+        for task in &open {
+            if task.annotations.contains_key("rec") {
+                if recurs_on(task, *day) {
+                    entries.push(task_entry(*day, task, "recurring "));
+                }
+            } else if task.due == Some(*day) {
+                entries.push(task_entry(*day, task, "due "));
+            } else if *day == today && task.due.is_some_and(|d| d < today) {
+                let mut entry = task_entry(*day, task, "overdue ");
+                if let Some(due) = task.due {
+                    entry
+                        .text
+                        .push_str(&format!(" (due {})", due.format("%Y-%m-%d")));
+                }
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+pub fn agenda(week: bool, format: String, config: Config) -> Result<(), io::Error> {
+    let today = Local::now().date_naive();
+    let days: Vec<NaiveDate> = if week {
+        let monday = today
+            .checked_sub_days(Days::new(today.weekday().num_days_from_monday() as u64))
+            .unwrap_or(today);
+        monday.iter_days().take(7).collect()
+    } else {
+        vec![today]
+    };
+    let entries = build(&days, &config)?;
+
+    match format.as_str() {
+        "list" => {
+            for day in &days {
+                let marker = if *day == today { " (today)" } else { "" };
+                println!("{}{}", day.format("%A, %B %d %Y"), marker);
+                for entry in entries.iter().filter(|e| e.day == *day) {
+                    println!("  {}", entry.text);
+                }
+            }
+        }
+        "picker" => {
+            let items: Vec<_> = entries
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.path.is_some())
+                .map(|(i, e)| (i, e.text.clone(), e.day.format("%a %m-%d").to_string()))
+                .collect();
+            if items.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "Nothing on the agenda.",
+                ));
+            }
+            cliclack::intro(console::style(" Grom ").on_cyan().black()).unwrap();
+            let selected = cliclack::select("Agenda".to_string())
+                .items(&items)
+                .interact()
+                .map_err(|_| io::Error::other("Error selecting agenda entry"))?;
+            let entry = &entries[selected];
+            if let Some(path) = &entry.path {
+                utils::open_file_at(&config.core.editor, path, entry.line)?;
+            }
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unknown output format.",
+            ))
+        }
+    }
+    Ok(())
+}
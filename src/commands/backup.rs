@@ -0,0 +1,198 @@
+use crate::core::config::{self, Config};
+use chrono::Local;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+const MANIFEST: &str = "MANIFEST.sha256";
+
+fn checksum(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+// Regular files below `dir`, relative to it. Symlinks are not followed.
+fn collect(
+    dir: &Path,
+    rel: &str,
+    skip: Option<&Path>,
+    files: &mut Vec<(String, PathBuf)>,
+) -> Result<(), io::Error> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.filter_map(Result::ok).collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let name = format!("{}{}", rel, entry.file_name().to_string_lossy());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect(&path, &format!("{}/", name), skip, files)?;
+        } else if file_type.is_file()
+            && skip.is_none_or(|s| fs::canonicalize(&path).ok().as_deref() != Some(s))
+        {
+            files.push((name, path));
+        }
+    }
+    Ok(())
+}
+
+pub fn backup(out: Option<String>, config: Config) -> Result<(), io::Error> {
+    let out = out.map(|o| config::expand_home(&o)).unwrap_or_else(|| {
+        format!(
+            "grom-backup-{}.tar.zst",
+            Local::now().format("%Y%m%d-%H%M%S")
+        )
+    });
+    let note_dir = Path::new(&config.core.note_dir);
+    if !note_dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Note directory does not exist.",
+        ));
+    }
+
+    let archive = File::create(&out)?;
+    // The archive itself must not end up in the backup when it is written into the note directory.
+    let own = fs::canonicalize(&out).ok();
+    let mut files = Vec::new();
+    collect(note_dir, "notes/", own.as_deref(), &mut files)?;
+    let config_dir = PathBuf::from(config::config_dir());
+    if config_dir.is_dir() {
+        collect(&config_dir, "config/", own.as_deref(), &mut files)?;
+    }
+
+    let mut builder = tar::Builder::new(zstd::Encoder::new(archive, 0)?.auto_finish());
+    let mut manifest = String::new();
+    for (name, path) in &files {
+        let data = fs::read(path)?;
+        let metadata = fs::metadata(path)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&metadata);
+        header.set_size(data.len() as u64);
+        builder.append_data(&mut header, name, data.as_slice())?;
+        manifest.push_str(&format!("{}  {}\n", checksum(&data), name));
+    }
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Local::now().timestamp() as u64);
+    builder.append_data(&mut header, MANIFEST, manifest.as_bytes())?;
+    builder.into_inner()?;
+
+    println!("{} files backed up to {}", files.len(), out);
+    Ok(())
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// Reads the whole archive into memory and checks every file against the manifest.
+fn verify(archive: &str) -> Result<BTreeMap<String, Vec<u8>>, io::Error> {
+    let mut tar = tar::Archive::new(zstd::Decoder::new(File::open(archive)?)?);
+    let mut files = BTreeMap::new();
+    let mut manifest = None;
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(invalid("Archive contains an unsafe path."));
+        }
+        let name = path.to_string_lossy().to_string();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        if name == MANIFEST {
+            manifest = Some(String::from_utf8_lossy(&data).to_string());
+        } else {
+            files.insert(name, data);
+        }
+    }
+    let manifest = manifest.ok_or_else(|| invalid("Archive has no manifest."))?;
+
+    let mut listed = 0;
+    for line in manifest.lines().filter(|l| !l.is_empty()) {
+        let (sum, name) = line
+            .split_once("  ")
+            .ok_or_else(|| invalid("Malformed manifest."))?;
+        match files.get(name) {
+            Some(data) if checksum(data) == sum => listed += 1,
+            Some(_) => return Err(invalid(&format!("Checksum mismatch for {}.", name))),
+            None => return Err(invalid(&format!("{} is missing from the archive.", name))),
+        }
+    }
+    if listed != files.len() {
+        return Err(invalid(
+            "Archive contains files that are not in the manifest.",
+        ));
+    }
+    Ok(files)
+}
+
+fn write(path: &Path, data: &[u8]) -> Result<(), io::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, data)
+}
+
+pub fn restore(archive: String, to: Option<String>) -> Result<(), io::Error> {
+    let files = verify(&config::expand_home(&archive))?;
+
+    // Without --to the notes go back to the configured note directory, or the one in the
+    // archived config on a machine that has no config yet.
+    let archived = files
+        .get("config/grom.toml")
+        .and_then(|c| toml::from_str::<Config>(&String::from_utf8_lossy(c)).ok())
+        .map(|c| config::expand_home(&c.core.note_dir));
+    let target = match to {
+        Some(to) => config::expand_home(&to),
+        None => match config::load_config() {
+            Ok(config) => config.core.note_dir,
+            Err(_) => archived
+                .clone()
+                .ok_or_else(|| invalid("No target directory, use --to."))?,
+        },
+    };
+    let target = PathBuf::from(target);
+    if target.exists() && (!target.is_dir() || fs::read_dir(&target)?.next().is_some()) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "Target directory is not empty.",
+        ));
+    }
+
+    let config_dir = PathBuf::from(config::config_dir());
+    let config_restored =
+        files.contains_key("config/grom.toml") && !config_dir.join("grom.toml").exists();
+    let (mut notes, mut restored_config, mut kept_config) = (0, 0, 0);
+    for (name, data) in &files {
+        if let Some(rel) = name.strip_prefix("notes/") {
+            write(&target.join(rel), data)?;
+            notes += 1;
+        } else if let Some(rel) = name.strip_prefix("config/") {
+            // An existing config is never overwritten.
+            let dest = config_dir.join(rel);
+            if dest.exists() {
+                kept_config += 1;
+            } else {
+                write(&dest, data)?;
+                restored_config += 1;
+            }
+        }
+    }
+
+    println!(
+        "{} files restored to {}, {} config files restored, {} existing config files kept",
+        notes,
+        target.display(),
+        restored_config,
+        kept_config
+    );
+    if archived.is_some_and(|a| Path::new(&a) != target) && config_restored {
+        println!("note_dir in the restored grom.toml still points to the original location");
+    }
+    Ok(())
+}
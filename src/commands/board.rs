@@ -0,0 +1,390 @@
+use crate::core::config::Config;
+use crate::core::markdown::{self, Heading};
+use crate::core::notes::Notebook;
+use crate::core::tasks::{self, Task};
+use crate::core::utils;
+use chrono::Local;
+use std::{fs, io};
+
+const COLUMNS: [&str; 3] = ["Todo", "Doing", "Done"];
+const CARD_WIDTH: usize = 40;
+
+struct Board {
+    columns: Vec<String>,
+    tasks: Vec<Task>,
+    placed: Vec<String>,
+}
+
+// Columns match headings and `status:` values by slug, so `## In Progress` is `status:in-progress`.
+fn same(a: &str, b: &str) -> bool {
+    utils::slugify(a) == utils::slugify(b)
+}
+
+fn heading_column<'a>(
+    headings: &[Heading],
+    line: usize,
+    columns: &'a [String],
+) -> Option<&'a String> {
+    let heading = headings.iter().rev().find(|h| h.line < line)?;
+    columns.iter().find(|c| same(c, &heading.text))
+}
+
+fn load(project: &str, config: &Config) -> Result<(Notebook, Board), io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    if notebook.project_start(project).is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Project does not exist.",
+        ));
+    }
+    let project_tasks: Vec<Task> = tasks::collect(&notebook)
+        .into_iter()
+        .filter(|t| t.in_project(project))
+        .collect();
+
+    let mut columns: Vec<String> = COLUMNS.iter().map(|c| c.to_string()).collect();
+    // Notes laid out as a board can add their own columns next to the default ones.
+    for note in &notebook.notes {
+        if note.project.as_deref() != Some(project) {
+            continue;
+        }
+        let headings = note.headings();
+        let Some(level) = headings
+            .iter()
+            .find(|h| columns.iter().any(|c| same(c, &h.text)))
+            .map(|h| h.level)
+        else {
+            continue;
+        };
+        let checkboxes = note.checkboxes();
+        for (i, heading) in headings.iter().enumerate() {
+            let end = headings[i + 1..]
+                .iter()
+                .find(|h| h.level <= heading.level)
+                .map(|h| h.line)
+                .unwrap_or(usize::MAX);
+            let has_tasks = checkboxes
+                .iter()
+                .any(|c| c.line > heading.line && c.line < end);
+            if heading.level == level
+                && has_tasks
+                && !columns.iter().any(|c| same(c, &heading.text))
+            {
+                columns.push(heading.text.clone());
+            }
+        }
+    }
+
+    // Statuses without a matching heading add columns of their own.
+    for task in &project_tasks {
+        if let Some(status) = task.annotations.get("status") {
+            if !columns.iter().any(|c| same(c, status)) {
+                columns.push(status.clone());
+            }
+        }
+    }
+
+    // A `status:` annotation wins over the heading a task sits under.
+    let mut placed = Vec::new();
+    for task in &project_tasks {
+        let headings = notebook
+            .find(&task.rel_path)
+            .map(|i| notebook.notes[i].headings())
+            .unwrap_or_default();
+        let column = task
+            .annotations
+            .get("status")
+            .and_then(|s| columns.iter().find(|c| same(c, s)))
+            .or_else(|| heading_column(&headings, task.line, &columns))
+            .cloned()
+            .unwrap_or_else(|| if task.done { "Done" } else { "Todo" }.to_string());
+        placed.push(column);
+    }
+    Ok((
+        notebook,
+        Board {
+            columns,
+            tasks: project_tasks,
+            placed,
+        },
+    ))
+}
+
+fn card(task: &Task) -> String {
+    let mut text = format!("{} ", task.id);
+    if let Some(priority) = task.priority {
+        text.push_str(&format!("({}) ", priority));
+    }
+    text.push_str(&task.description);
+    if text.chars().count() > CARD_WIDTH {
+        text = text.chars().take(CARD_WIDTH - 1).collect::<String>() + "…";
+    }
+    text
+}
+
+fn render(board: &Board) {
+    let columns: Vec<Vec<String>> = board
+        .columns
+        .iter()
+        .map(|c| {
+            board
+                .tasks
+                .iter()
+                .zip(&board.placed)
+                .filter(|(_, column)| *column == c)
+                .map(|(task, _)| card(task))
+                .collect()
+        })
+        .collect();
+    let header: Vec<String> = board
+        .columns
+        .iter()
+        .zip(&columns)
+        .map(|(name, cards)| format!("{} ({})", name, cards.len()))
+        .collect();
+    let height = columns.iter().map(|c| c.len()).max().unwrap_or(0);
+    let rows: Vec<Vec<String>> = (0..height)
+        .map(|i| {
+            columns
+                .iter()
+                .map(|c| c.get(i).cloned().unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    let header: Vec<&str> = header.iter().map(|h| h.as_str()).collect();
+    utils::print_table(&header, &rows);
+}
+
+// Checking a card into Done completes it, taking it out of Done reopens it.
+fn checkbox_state(line: &str, done: bool, column: &str) -> String {
+    let updated = if same(column, "done") && !done {
+        tasks::complete_line(line, Local::now().date_naive())
+    } else if !same(column, "done") && done {
+        tasks::reopen_line(line)
+    } else {
+        None
+    };
+    updated.unwrap_or_else(|| line.to_string())
+}
+
+fn with_status(line: &str, column: &str) -> String {
+    let status = format!("status:{}", utils::slugify(column));
+    if line.split_whitespace().any(|w| w.starts_with("status:")) {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let words: Vec<&str> = line
+            .split_whitespace()
+            .map(|w| {
+                if w.starts_with("status:") {
+                    status.as_str()
+                } else {
+                    w
+                }
+            })
+            .collect();
+        format!("{}{}", indent, words.join(" "))
+    } else {
+        format!("{} {}", line.trim_end(), status)
+    }
+}
+
+fn move_card(
+    notebook: &Notebook,
+    board: &Board,
+    task: &Task,
+    column: &str,
+) -> Result<(), io::Error> {
+    let content = fs::read_to_string(&task.path)?;
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let source = lines
+        .get(task.line - 1)
+        .cloned()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Task line no longer exists."))?;
+    let line = checkbox_state(&source, task.done, column);
+
+    let headings = notebook
+        .find(&task.rel_path)
+        .map(|i| notebook.notes[i].headings())
+        .unwrap_or_default();
+    let uses_headings = headings
+        .iter()
+        .any(|h| board.columns.iter().any(|c| same(c, &h.text)));
+
+    if task.annotations.contains_key("status") || !uses_headings {
+        let implied = same(column, "todo") || same(column, "done");
+        let line = if task.annotations.contains_key("status") || !implied {
+            with_status(&line, column)
+        } else {
+            line
+        };
+        return tasks::replace_line(&task.path, task.line, Some(&line));
+    }
+
+    // The card moves with its sub-items and continuation lines, keeping their indentation
+    // relative to it.
+    let start = task.line - 1;
+    let end = {
+        let refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+        task.line + tasks::nested(&refs, start)
+    };
+    let depth = source.len() - source.trim_start().len();
+
+    // Cards go below the last task of the column's section and its sub-items, creating the
+    // section if needed.
+    let position = match headings.iter().position(|h| same(&h.text, column)) {
+        Some(i) => {
+            let heading = &headings[i];
+            let section_end = headings[i + 1..]
+                .iter()
+                .find(|h| h.level <= heading.level)
+                .map(|h| h.line)
+                .unwrap_or(lines.len() + 1);
+            let last_task = (heading.line + 1..section_end).rev().find(|l| {
+                !(start + 1..=end).contains(l) && markdown::checkbox(&lines[l - 1]).is_some()
+            });
+            match last_task {
+                Some(l) => {
+                    let refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+                    l + tasks::nested(&refs, l - 1)
+                }
+                None => heading.line,
+            }
+        }
+        None => {
+            let level = headings
+                .iter()
+                .find(|h| board.columns.iter().any(|c| same(c, &h.text)))
+                .map(|h| h.level)
+                .unwrap_or(2);
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("{} {}", "#".repeat(level), column));
+            lines.len()
+        }
+    };
+    let mut block: Vec<String> = lines
+        .drain(start..end)
+        .map(|l| l.get(depth..).unwrap_or(l.trim_start()).to_string())
+        .collect();
+    block[0] = line.trim_start().to_string();
+    let position = if position > start {
+        position - block.len()
+    } else {
+        position
+    };
+    lines.splice(position..position, block);
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    tasks::write_note(&task.path, &updated)
+}
+
+fn column_name(board: &Board, column: &str) -> String {
+    board
+        .columns
+        .iter()
+        .find(|c| same(c, column))
+        .cloned()
+        .unwrap_or_else(|| column.to_string())
+}
+
+fn interactive(project: &str, config: &Config) -> Result<(), io::Error> {
+    cliclack::intro(console::style(" Grom ").on_cyan().black()).unwrap();
+    loop {
+        let (notebook, board) = load(project, config)?;
+        render(&board);
+        if board.tasks.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "No tasks found."));
+        }
+        let mut items: Vec<(usize, String, String)> = board
+            .tasks
+            .iter()
+            .zip(&board.placed)
+            .enumerate()
+            .map(|(i, (task, column))| (i, card(task), column.clone()))
+            .collect();
+        items.push((board.tasks.len(), "Quit".to_string(), String::new()));
+        let selected = cliclack::select("Select a card to move".to_string())
+            .items(&items)
+            .interact()
+            .map_err(|_| io::Error::other("Error selecting card"))?;
+        if selected == board.tasks.len() {
+            return Ok(());
+        }
+        let (task, current) = (&board.tasks[selected], &board.placed[selected]);
+        let columns: Vec<(String, String, String)> = board
+            .columns
+            .iter()
+            .filter(|c| *c != current)
+            .map(|c| (c.clone(), c.clone(), String::new()))
+            .collect();
+        let column = cliclack::select("Move to".to_string())
+            .items(&columns)
+            .interact()
+            .map_err(|_| io::Error::other("Error selecting column"))?;
+        move_card(&notebook, &board, task, &column)?;
+    }
+}
+
+pub fn board(
+    project: String,
+    move_to: Option<Vec<String>>,
+    interact: bool,
+    config: Config,
+) -> Result<(), io::Error> {
+    if interact {
+        return interactive(&project, &config);
+    }
+    if let Some(args) = move_to {
+        let (id, column) = match args.as_slice() {
+            [id, column] => (id, column),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Expected a task id and a column.",
+                ))
+            }
+        };
+        let (notebook, board) = load(&project, &config)?;
+        let task = tasks::find(&board.tasks, id)?;
+        let current = board
+            .tasks
+            .iter()
+            .position(|t| t.id == task.id)
+            .map(|i| board.placed[i].clone())
+            .unwrap_or_default();
+        let column = column_name(&board, column);
+        if !same(&current, &column) {
+            move_card(&notebook, &board, task, &column)?;
+        }
+    }
+    let (_, board) = load(&project, &config)?;
+    render(&board);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::TempNotebook;
+
+    #[test]
+    fn cards_move_with_their_sub_items() {
+        let temp = TempNotebook::new(&[(
+            "projects/acme/start.md",
+            "# Acme\n\n## Todo\n- [ ] Write spec\n  - [ ] Outline\n    notes on scope\n\
+             - [ ] Ship it\n  - [ ] Tag release\n\n## Doing\n- [ ] Review\n  - [ ] Read diff\n",
+        )]);
+        let config: Config =
+            toml::from_str(&format!("[core]\nnote_dir = {:?}\n", temp.dir())).unwrap();
+        let (notebook, board) = load("acme", &config).unwrap();
+        let task = board.tasks.iter().find(|t| t.text == "Write spec").unwrap();
+        move_card(&notebook, &board, task, "Doing").unwrap();
+
+        let moved = fs::read_to_string(temp.path().join("projects/acme/start.md")).unwrap();
+        assert_eq!(
+            moved,
+            "# Acme\n\n## Todo\n- [ ] Ship it\n  - [ ] Tag release\n\n## Doing\n- [ ] Review\n  \
+             - [ ] Read diff\n- [ ] Write spec\n  - [ ] Outline\n    notes on scope\n"
+        );
+    }
+}
@@ -1,54 +1,208 @@
 use crate::core::config::Config;
-use crate::core::utils;
-use chrono::{Datelike, Local};
+use crate::core::notes::{self, DiaryPeriod, Notebook};
+use crate::core::{html, markdown, meta, tasks, utils};
+use chrono::{Days, Local, NaiveDate, Weekday};
+use std::{fs, io};
 
 pub fn daily_diary(config: Config) -> Result<(), std::io::Error> {
-    let today = Local::now();
-    let file = format!(
-        "{}/diary/{}/{}/week{}/{}.md",
-        config.core.note_dir,
-        today.year(),
-        today.format("%B"),
-        today.iso_week().week(),
-        today.format("%m-%d-%Y")
-    );
+    let today = Local::now().date_naive();
+    let file = notes::daily_diary_path(&config.core.note_dir, today);
     if utils::path_exists(&file) {
         return utils::open_file(&config.core.editor, &file) 
     }
     utils::ensure_all_dirs(&file)?;
-    utils::save_file(&file)?;
+    let title = notes::diary_title(DiaryPeriod::Daily, today);
+    utils::save_file(&file, &meta::initial(&title, &config.core.front_matter)?)?;
     utils::open_file(&config.core.editor, &file)
 }
 
 pub fn weekly_diary(config: Config) -> Result<(), std::io::Error>{
-    let today = Local::now();
-    let file = format!(
-        "{}/diary/{}/{}/week{}/week.md",
-        config.core.note_dir,
-        today.year(),
-        today.format("%B"),
-        today.iso_week().week(),
-    );
+    let today = Local::now().date_naive();
+    let file = notes::weekly_diary_path(&config.core.note_dir, today);
     if utils::path_exists(file.clone()) {
         return utils::open_file(&config.core.editor, &file)
     }
     utils::ensure_all_dirs(&file)?;
-    utils::save_file(&file)?;
+    let title = notes::diary_title(DiaryPeriod::Weekly, today);
+    utils::save_file(&file, &meta::initial(&title, &config.core.front_matter)?)?;
     utils::open_file(&config.core.editor, &file)
 }
 
 pub fn monthly_diary(config: Config) -> Result<(), std::io::Error>{
-    let today = Local::now();
-    let file = format!(
-        "{}/diary/{}/{}/month.md",
-        config.core.note_dir,
-        today.year(),
-        today.format("%B"),
-    );
+    let today = Local::now().date_naive();
+    let file = notes::monthly_diary_path(&config.core.note_dir, today);
     if utils::path_exists(file.clone()) {
         return utils::open_file(&config.core.editor, &file)
     }
     utils::ensure_all_dirs(&file)?;
-    utils::save_file(&file)?;
+    let title = notes::diary_title(DiaryPeriod::Monthly, today);
+    utils::save_file(&file, &meta::initial(&title, &config.core.front_matter)?)?;
     utils::open_file(&config.core.editor, &file)
 }
+
+pub struct Period {
+    pub month: Option<String>,
+    pub year: Option<String>,
+    pub week: Option<String>,
+    pub range: Option<String>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_string())
+}
+
+// Returns the first and last day of the period together with its title.
+fn bounds(period: &Period) -> Result<(NaiveDate, NaiveDate, String), io::Error> {
+    let today = Local::now().date_naive();
+    match period {
+        Period {
+            month: Some(month), ..
+        } => {
+            let (year, month) = month
+                .split_once('-')
+                .and_then(|(y, m)| Some((y.parse().ok()?, m.parse().ok()?)))
+                .ok_or_else(|| invalid("Invalid month, expected YYYY-MM."))?;
+            let (start, end) =
+                notes::month_bounds(year, month).ok_or_else(|| invalid("Invalid month."))?;
+            Ok((start, end, start.format("%B %Y").to_string()))
+        }
+        Period {
+            year: Some(year), ..
+        } => {
+            let year: i32 = year
+                .parse()
+                .map_err(|_| invalid("Invalid year, expected YYYY."))?;
+            let start =
+                NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(|| invalid("Invalid year."))?;
+            let end =
+                NaiveDate::from_ymd_opt(year, 12, 31).ok_or_else(|| invalid("Invalid year."))?;
+            Ok((start, end, year.to_string()))
+        }
+        Period {
+            week: Some(week), ..
+        } => {
+            let (year, week) = week
+                .split_once('-')
+                .and_then(|(y, w)| {
+                    Some((
+                        y.parse().ok()?,
+                        w.trim_start_matches(['W', 'w']).parse().ok()?,
+                    ))
+                })
+                .ok_or_else(|| invalid("Invalid week, expected YYYY-Www."))?;
+            let start = NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)
+                .ok_or_else(|| invalid("Invalid week."))?;
+            let end = start + Days::new(6);
+            Ok((start, end, notes::diary_title(DiaryPeriod::Weekly, start)))
+        }
+        Period {
+            range: Some(range), ..
+        } => {
+            let (start, end) = range
+                .split_once("..")
+                .and_then(|(s, e)| {
+                    Some((
+                        tasks::parse_relative_date(s, today)?,
+                        tasks::parse_relative_date(e, today)?,
+                    ))
+                })
+                .ok_or_else(|| invalid("Invalid range, expected START..END."))?;
+            if end < start {
+                return Err(invalid("Range ends before it starts."));
+            }
+            Ok((
+                start,
+                end,
+                format!("{} to {}", start.format("%Y-%m-%d"), end.format("%Y-%m-%d")),
+            ))
+        }
+        _ => Err(invalid(
+            "Expected one of --month, --year, --week or --range.",
+        )),
+    }
+}
+
+pub fn compile(
+    period: Period,
+    format: String,
+    out: Option<String>,
+    config: Config,
+) -> Result<(), io::Error> {
+    let (start, end, name) = bounds(&period)?;
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let entries = notebook.diary(start, end);
+    if entries.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No diary entries in this period.",
+        ));
+    }
+    let html = match format.as_str() {
+        "markdown" => false,
+        "html" => true,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unknown output format.",
+            ))
+        }
+    };
+
+    let title = format!("Diary: {}", name);
+    let mut toc = String::from("## Contents\n\n");
+    let mut body = String::new();
+    for i in &entries {
+        let note = &notebook.notes[*i];
+        let period = note.period.unwrap_or(DiaryPeriod::Daily);
+        let heading = notes::diary_title(period, note.date.unwrap_or(start));
+        let anchor = utils::slugify(&heading);
+        let indent = if period == DiaryPeriod::Daily {
+            "  "
+        } else {
+            ""
+        };
+        toc.push_str(&format!("{}- [{}](#{})\n", indent, heading, anchor));
+        if html {
+            body.push_str(&format!("## {} {{#{}}}\n\n", heading, anchor));
+        } else {
+            body.push_str(&format!("## {}\n\n", heading));
+        }
+        let content = markdown::demote_headings(note.body().trim(), 2);
+        // Links are relative to each note, so they point at absolute paths in the compiled
+        // document. Links between notes have nowhere to point in a single HTML page.
+        let dir = std::path::absolute(notebook.root.join(&note.rel_path))?;
+        let dir = dir.parent().unwrap_or(&dir);
+        let content = markdown::map_links(&content, &mut |link| match notebook.resolve(*i, link) {
+            Some(linked) if !html => {
+                std::path::absolute(notebook.root.join(&notebook.notes[linked].rel_path))
+                    .ok()
+                    .map(|p| p.display().to_string())
+            }
+            Some(_) => None,
+            None if !link.target.ends_with(".md")
+                && (!link.wiki || dir.join(&link.target).is_file()) =>
+            {
+                Some(dir.join(&link.target).display().to_string())
+            }
+            None => None,
+        });
+        body.push_str(content.trim_end());
+        body.push_str("\n\n");
+    }
+    let document = format!("# {}\n\n{}\n{}", title, toc, body);
+    let output = if html {
+        html::standalone(&title, &html::render_markdown(&document))
+    } else {
+        document
+    };
+
+    match out {
+        Some(path) => {
+            utils::ensure_all_dirs(&path)?;
+            fs::write(&path, output)?;
+            println!("{}", path);
+        }
+        None => print!("{}", output),
+    }
+    Ok(())
+}
@@ -0,0 +1,452 @@
+use crate::core::config::Config;
+use crate::core::epub::{Book, Chapter, Resource};
+use crate::core::html;
+use crate::core::ics::{self, Calendar};
+use crate::core::meta::{self, NoteMeta};
+use crate::core::notes::{self, DiaryPeriod, Note, Notebook};
+use crate::core::tasks;
+use crate::core::utils;
+use chrono::{Local, NaiveDate, TimeDelta, TimeZone, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::{fs, io};
+
+fn priority(value: char) -> u8 {
+    match value {
+        'A' => 1,
+        'B' => 5,
+        _ => 9,
+    }
+}
+
+pub fn ics(out: Option<String>, config: Config) -> Result<(), io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let stamp = format!("{}Z", ics::datetime(Utc::now().naive_utc()));
+    let mut calendar = Calendar::new();
+
+    for task in tasks::collect(&notebook) {
+        let Some(due) = task.due else {
+            continue;
+        };
+        let mut properties = vec![
+            ("UID".to_string(), format!("{}@grom", task.id)),
+            ("DTSTAMP".to_string(), stamp.clone()),
+            ("SUMMARY".to_string(), ics::escape(&task.description)),
+            ("DUE;VALUE=DATE".to_string(), ics::date(due)),
+        ];
+        if task.done {
+            properties.push(("STATUS".to_string(), "COMPLETED".to_string()));
+            if let Some(done) = task
+                .annotations
+                .get("done")
+                .and_then(|d| tasks::parse_date(d))
+            {
+                properties.push(("COMPLETED".to_string(), ics::date(done) + "T000000Z"));
+            }
+        } else {
+            properties.push(("STATUS".to_string(), "NEEDS-ACTION".to_string()));
+        }
+        if let Some(rule) = task.annotations.get("rec") {
+            let freq = match rule.as_str() {
+                "daily" | "weekly" | "monthly" | "yearly" => Some(rule.to_uppercase()),
+                _ => None,
+            };
+            if let Some(freq) = freq {
+                properties.push(("RRULE".to_string(), format!("FREQ={}", freq)));
+            }
+        }
+        if let Some(p) = task.priority {
+            properties.push(("PRIORITY".to_string(), priority(p).to_string()));
+        }
+        if !task.tags.is_empty() {
+            let categories: Vec<String> = task.tags.iter().map(|t| ics::escape(t)).collect();
+            properties.push(("CATEGORIES".to_string(), categories.join(",")));
+        }
+        properties.push(("DESCRIPTION".to_string(), ics::escape(&task.rel_path)));
+        calendar.component("VTODO", &properties);
+    }
+
+    for note in &notebook.notes {
+        let (Some(DiaryPeriod::Daily), Some(day)) = (note.period, note.date) else {
+            continue;
+        };
+        for entry in note.timed_entries() {
+            let start = day.and_time(entry.start);
+            let end = match entry.end {
+                Some(end) if end > entry.start => day.and_time(end),
+                _ => start + TimeDelta::hours(1),
+            };
+            // Stable while lines are added around the entry, so subscribed calendars update it.
+            let uid = format!(
+                "{:x}",
+                Sha256::digest(format!(
+                    "{}\n{}\n{}",
+                    note.rel_path,
+                    ics::datetime(start),
+                    entry.text
+                ))
+            );
+            calendar.component(
+                "VEVENT",
+                &[
+                    ("UID".to_string(), format!("{}@grom", &uid[..32])),
+                    ("DTSTAMP".to_string(), stamp.clone()),
+                    ("DTSTART".to_string(), ics::datetime(start)),
+                    ("DTEND".to_string(), ics::datetime(end)),
+                    ("SUMMARY".to_string(), ics::escape(&entry.text)),
+                    ("DESCRIPTION".to_string(), ics::escape(&note.rel_path)),
+                ],
+            );
+        }
+    }
+
+    // Not inside note_dir, where sync would commit the generated file.
+    let out = out.unwrap_or("grom.ics".to_string());
+    utils::ensure_all_dirs(&out)?;
+    fs::write(&out, calendar.render())?;
+    println!("{}", out);
+    Ok(())
+}
+
+pub fn html(out_dir: String, config: Config) -> Result<(), io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let all: Vec<usize> = (0..notebook.notes.len()).collect();
+    let pages = html::write_site(&notebook, &all, Path::new(&out_dir), "Notes")?;
+    println!("{} pages written to {}", pages, out_dir);
+    Ok(())
+}
+
+fn rfc3339(date: NaiveDate) -> String {
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_default()
+}
+
+fn entry_title(note: &Note, date: NaiveDate) -> String {
+    if let Some(title) = &note.meta.title {
+        return title.clone();
+    }
+    if let Some(heading) = note.headings().first() {
+        return heading.text.clone();
+    }
+    notes::diary_title(note.period.unwrap_or(DiaryPeriod::Daily), date)
+}
+
+pub fn feed(
+    out: Option<String>,
+    period: Option<String>,
+    limit: Option<usize>,
+    config: Config,
+) -> Result<(), io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let period = match period.as_deref().unwrap_or(&config.feed.period) {
+        "daily" => DiaryPeriod::Daily,
+        "weekly" => DiaryPeriod::Weekly,
+        "monthly" => DiaryPeriod::Monthly,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unknown diary period.",
+            ))
+        }
+    };
+    let mut entries: Vec<(NaiveDate, usize)> = notebook
+        .notes
+        .iter()
+        .enumerate()
+        .filter(|(_, n)| n.period == Some(period))
+        .filter_map(|(i, n)| n.date.map(|d| (d, i)))
+        .collect();
+    entries.sort_by(|a, b| b.cmp(a));
+    entries.truncate(limit.unwrap_or(config.feed.limit));
+
+    let url = config.feed.url.as_deref().map(|u| u.trim_end_matches('/'));
+    let updated = entries
+        .first()
+        .map(|(d, _)| rfc3339(*d))
+        .unwrap_or_else(|| Local::now().to_rfc3339());
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!(
+        "  <title>{}</title>\n",
+        html::escape(&config.feed.title)
+    ));
+    xml.push_str(&format!(
+        "  <id>{}</id>\n",
+        html::escape(url.unwrap_or("urn:grom:diary"))
+    ));
+    if let Some(url) = url {
+        xml.push_str(&format!("  <link href=\"{}/\"/>\n", html::escape(url)));
+    }
+    xml.push_str(&format!("  <updated>{}</updated>\n", updated));
+    xml.push_str(&format!(
+        "  <author><name>{}</name></author>\n",
+        html::escape(config.feed.author.as_deref().unwrap_or("grom"))
+    ));
+    for (date, i) in entries {
+        let note = &notebook.notes[i];
+        let page = html::page_path(&note.rel_path);
+        let content = html::render(&notebook, i, &|_| None, None).html;
+        // Relative image urls resolve against the entry's page of the exported site.
+        match url {
+            Some(url) => xml.push_str(&format!(
+                "  <entry xml:base=\"{}\">\n",
+                html::escape(&format!("{}/{}", url, page))
+            )),
+            None => xml.push_str("  <entry>\n"),
+        }
+        xml.push_str(&format!(
+            "    <title>{}</title>\n",
+            html::escape(&entry_title(note, date))
+        ));
+        match url {
+            Some(url) => {
+                let link = html::escape(&format!("{}/{}", url, page));
+                xml.push_str(&format!("    <id>{}</id>\n", link));
+                xml.push_str(&format!("    <link href=\"{}\"/>\n", link));
+            }
+            None => xml.push_str(&format!(
+                "    <id>urn:grom:{}</id>\n",
+                html::escape(&note.rel_path)
+            )),
+        }
+        xml.push_str(&format!("    <published>{}</published>\n", rfc3339(date)));
+        xml.push_str(&format!("    <updated>{}</updated>\n", rfc3339(date)));
+        xml.push_str(&format!(
+            "    <content type=\"html\">{}</content>\n",
+            html::escape(&content)
+        ));
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+
+    // Not inside note_dir, where sync would commit the generated file.
+    let out = out.unwrap_or("feed.xml".to_string());
+    utils::ensure_all_dirs(&out)?;
+    fs::write(&out, xml)?;
+    println!("{}", out);
+    Ok(())
+}
+
+fn diary_bounds(period: &str) -> Option<(NaiveDate, NaiveDate)> {
+    match period.split_once('-') {
+        Some((year, month)) => notes::month_bounds(year.parse().ok()?, month.parse().ok()?),
+        None => {
+            let year = period.parse().ok()?;
+            Some((
+                NaiveDate::from_ymd_opt(year, 1, 1)?,
+                NaiveDate::from_ymd_opt(year, 12, 31)?,
+            ))
+        }
+    }
+}
+
+pub fn epub(
+    project: Option<String>,
+    diary: Option<String>,
+    out: Option<String>,
+    config: Config,
+) -> Result<(), io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let (title, chapters) = match (&project, &diary) {
+        (Some(project), None) => {
+            let start = notebook.project_start(project).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "Project does not exist.")
+            })?;
+            let mut chapters: Vec<usize> = (0..notebook.notes.len())
+                .filter(|i| notebook.notes[*i].project.as_ref() == Some(project))
+                .collect();
+            chapters.sort_by_key(|i| (*i != start, notebook.notes[*i].rel_path.clone()));
+            (notebook.notes[start].title(), chapters)
+        }
+        (None, Some(period)) => {
+            let (start, end) = diary_bounds(period).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Invalid diary period, expected YYYY or YYYY-MM.",
+                )
+            })?;
+            (format!("Diary {}", period), notebook.diary(start, end))
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Expected either --project or --diary.",
+            ))
+        }
+    };
+    if chapters.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "No notes found."));
+    }
+
+    let file = |n: usize| format!("chapters/{:03}.xhtml", n + 1);
+    let position = |i: usize| chapters.iter().position(|c| *c == i);
+
+    // A first pass collects the assets so every one of them gets a stable file name.
+    let mut assets: Vec<String> = Vec::new();
+    for i in &chapters {
+        for asset in html::render(&notebook, *i, &|_| None, None).assets {
+            if !assets.contains(&asset) && notebook.root.join(&asset).is_file() {
+                assets.push(asset);
+            }
+        }
+    }
+    let resource = |asset: &str| match assets.iter().position(|a| a == asset) {
+        Some(n) => {
+            let extension = asset.rsplit_once('.').map(|(_, e)| e).unwrap_or("bin");
+            format!("resources/{:03}.{}", n + 1, extension)
+        }
+        None => asset.to_string(),
+    };
+
+    let mut book = Book {
+        title,
+        chapters: Vec::new(),
+        resources: Vec::new(),
+    };
+    for (n, i) in chapters.iter().enumerate() {
+        let note = &notebook.notes[*i];
+        let target = |j: usize| position(j).map(|p| file(p).replacen("chapters/", "", 1));
+        let relative = |asset: &str| format!("../{}", resource(asset));
+        let rendered = html::render(&notebook, *i, &target, Some(&relative));
+        let title = match (&note.meta.title, note.period, note.date) {
+            (None, Some(period), Some(date)) => notes::diary_title(period, date),
+            _ => note.title(),
+        };
+        // Notes that open with a top-level heading already carry their chapter title.
+        let titled = note
+            .headings()
+            .first()
+            .is_some_and(|h| h.level == 1 && note.body().trim_start().starts_with('#'));
+        let body = if titled {
+            rendered.html
+        } else {
+            format!("<h1>{}</h1>\n{}", html::escape(&title), rendered.html)
+        };
+        book.chapters.push(Chapter {
+            body,
+            title,
+            file: file(n),
+        });
+    }
+    for asset in &assets {
+        book.resources.push(Resource {
+            file: resource(asset),
+            data: fs::read(notebook.root.join(asset))?,
+        });
+    }
+
+    let out = out.unwrap_or(format!(
+        "{}/{}.epub",
+        config.core.note_dir,
+        utils::slugify(&book.title)
+    ));
+    utils::ensure_all_dirs(&out)?;
+    book.write(&out)?;
+    println!("{}", out);
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct LinkRecord {
+    target: String,
+    wiki: bool,
+    resolved: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TaskRecord {
+    id: String,
+    line: usize,
+    done: bool,
+    description: String,
+    priority: Option<char>,
+    due: Option<String>,
+    tags: Vec<String>,
+    annotations: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct NoteRecord<'a> {
+    path: &'a str,
+    kind: &'static str,
+    title: String,
+    period: Option<&'static str>,
+    date: Option<String>,
+    project: Option<&'a str>,
+    front_matter: Option<&'a NoteMeta>,
+    tags: Vec<String>,
+    links: Vec<LinkRecord>,
+    tasks: Vec<TaskRecord>,
+    body: &'a str,
+}
+
+pub fn json(ndjson: bool, out: Option<String>, config: Config) -> Result<(), io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let mut tasks: BTreeMap<String, Vec<TaskRecord>> = BTreeMap::new();
+    for task in tasks::collect(&notebook) {
+        tasks.entry(task.rel_path).or_default().push(TaskRecord {
+            id: task.id,
+            line: task.line,
+            done: task.done,
+            description: task.description,
+            priority: task.priority,
+            due: task.due.map(|d| d.format("%Y-%m-%d").to_string()),
+            tags: task.tags,
+            annotations: task.annotations,
+        });
+    }
+
+    let mut records = Vec::new();
+    for (i, note) in notebook.notes.iter().enumerate() {
+        let links = note
+            .links()
+            .into_iter()
+            .map(|link| LinkRecord {
+                resolved: notebook
+                    .resolve(i, &link)
+                    .map(|t| notebook.notes[t].rel_path.clone()),
+                target: link.target,
+                wiki: link.wiki,
+            })
+            .collect();
+        records.push(NoteRecord {
+            path: &note.rel_path,
+            kind: note.kind.as_str(),
+            title: note.title(),
+            period: note.period.map(|p| p.as_str()),
+            date: note.date.map(|d| d.format("%Y-%m-%d").to_string()),
+            project: note.project.as_deref(),
+            front_matter: meta::split(&note.content).map(|_| &note.meta),
+            tags: note.tags(),
+            links,
+            tasks: tasks.remove(&note.rel_path).unwrap_or_default(),
+            body: note.body(),
+        });
+    }
+
+    // NDJSON has one record per line, for tools that stream the notebook.
+    let output = if ndjson {
+        let mut lines = String::new();
+        for record in &records {
+            lines.push_str(&serde_json::to_string(record)?);
+            lines.push('\n');
+        }
+        lines
+    } else {
+        format!("{}\n", serde_json::to_string_pretty(&records)?)
+    };
+    match out {
+        Some(out) => {
+            utils::ensure_all_dirs(&out)?;
+            fs::write(&out, output)?;
+            println!("{} notes exported to {}", records.len(), out);
+        }
+        None => print!("{}", output),
+    }
+    Ok(())
+}
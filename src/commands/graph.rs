@@ -0,0 +1,153 @@
+use crate::core::config::Config;
+use crate::core::notes::Notebook;
+use serde::Serialize;
+use std::io;
+
+#[derive(Serialize)]
+struct Node {
+    id: String,
+    label: String,
+    kind: String,
+    tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Edge {
+    source: String,
+    target: String,
+    kind: String,
+}
+
+#[derive(Serialize)]
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+fn build(notebook: &Notebook) -> Graph {
+    let nodes = notebook
+        .notes
+        .iter()
+        .map(|n| Node {
+            id: n.rel_path.clone(),
+            label: n.title(),
+            kind: n.kind.as_str().to_string(),
+            tags: n.tags(),
+        })
+        .collect();
+
+    let mut edges: Vec<Edge> = Vec::new();
+    for (i, note) in notebook.notes.iter().enumerate() {
+        for link in note.links() {
+            if let Some(target) = notebook.resolve(i, &link) {
+                let target = notebook.notes[target].rel_path.clone();
+                let duplicate = edges
+                    .iter()
+                    .any(|e| e.source == note.rel_path && e.target == target && e.kind == "link");
+                if target != note.rel_path && !duplicate {
+                    edges.push(Edge {
+                        source: note.rel_path.clone(),
+                        target,
+                        kind: "link".to_string(),
+                    });
+                }
+            }
+        }
+        if let Some(project) = &note.project {
+            if let Some(start) = notebook.project_start(project) {
+                if start != i {
+                    edges.push(Edge {
+                        source: note.rel_path.clone(),
+                        target: notebook.notes[start].rel_path.clone(),
+                        kind: "project".to_string(),
+                    });
+                }
+            }
+        }
+    }
+    Graph { nodes, edges }
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn to_dot(graph: &Graph) -> String {
+    let mut out = String::from("digraph grom {\n");
+    for node in &graph.nodes {
+        out.push_str(&format!(
+            "  \"{}\" [label=\"{}\", kind=\"{}\", tags=\"{}\"];\n",
+            escape_dot(&node.id),
+            escape_dot(&node.label),
+            node.kind,
+            escape_dot(&node.tags.join(",")),
+        ));
+    }
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "  \"{}\" -> \"{}\" [kind=\"{}\"];\n",
+            escape_dot(&edge.source),
+            escape_dot(&edge.target),
+            edge.kind,
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn to_graphml(graph: &Graph) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+        "  <key id=\"tags\" for=\"node\" attr.name=\"tags\" attr.type=\"string\"/>\n",
+        "  <key id=\"edge_kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+        "  <graph id=\"grom\" edgedefault=\"directed\">\n",
+    ));
+    for node in &graph.nodes {
+        out.push_str(&format!(
+            "    <node id=\"{}\">\n      <data key=\"label\">{}</data>\n      <data key=\"kind\">{}</data>\n      <data key=\"tags\">{}</data>\n    </node>\n",
+            escape_xml(&node.id),
+            escape_xml(&node.label),
+            node.kind,
+            escape_xml(&node.tags.join(",")),
+        ));
+    }
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\">\n      <data key=\"edge_kind\">{}</data>\n    </edge>\n",
+            escape_xml(&edge.source),
+            escape_xml(&edge.target),
+            edge.kind,
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+pub fn graph(format: String, config: Config) -> Result<(), io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let graph = build(&notebook);
+    let output = match format.as_str() {
+        "dot" => to_dot(&graph),
+        "json" => serde_json::to_string_pretty(&graph)?,
+        "graphml" => to_graphml(&graph),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unknown graph format.",
+            ))
+        }
+    };
+    println!("{}", output.trim_end());
+    Ok(())
+}
@@ -0,0 +1,1094 @@
+use crate::core::config::{self, Config};
+use crate::core::meta::{self, Format};
+use crate::core::notes::{self, DiaryPeriod};
+use crate::core::utils;
+use crate::core::{enex, enml, joplin};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+// Converts a moment.js date format, as used by Obsidian, into a chrono format.
+fn chrono_format(moment: &str) -> String {
+    let tokens = [
+        ("YYYY", "%Y"),
+        ("YY", "%y"),
+        ("MMMM", "%B"),
+        ("MMM", "%b"),
+        ("MM", "%m"),
+        ("M", "%m"),
+        ("DD", "%d"),
+        ("D", "%d"),
+        ("dddd", "%A"),
+        ("ddd", "%a"),
+    ];
+    let mut out = String::new();
+    let mut rest = moment;
+    'outer: while !rest.is_empty() {
+        if let Some(literal) = rest.strip_prefix('[') {
+            let end = literal.find(']').unwrap_or(literal.len());
+            out.push_str(&literal[..end].replace('%', "%%"));
+            rest = literal.get(end + 1..).unwrap_or("");
+            continue;
+        }
+        for (token, format) in tokens {
+            if let Some(after) = rest.strip_prefix(token) {
+                out.push_str(format);
+                rest = after;
+                continue 'outer;
+            }
+        }
+        let c = rest.chars().next().unwrap_or_default();
+        if c == '%' {
+            out.push('%');
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+fn read_json(path: &Path) -> serde_json::Value {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), io::Error> {
+    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+        let path = entry.path();
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(rel) = path.strip_prefix(root) {
+            files.push(rel.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+/// Path of `to` relative to the folder of `from`, both relative to the notebook root.
+fn relative(from: &str, to: &str) -> String {
+    let from: Vec<&str> = from.split('/').collect();
+    let from = &from[..from.len() - 1];
+    let to: Vec<&str> = to.split('/').collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<&str> = vec![".."; from.len() - common];
+    parts.extend(&to[common..]);
+    parts.join("/")
+}
+
+fn normalize(path: &Path) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                parts.pop();
+            }
+            std::path::Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            _ => {}
+        }
+    }
+    parts.join("/")
+}
+
+fn markdown_url(path: &str) -> String {
+    if path.contains(' ') {
+        format!("<{}>", path)
+    } else {
+        path.to_string()
+    }
+}
+
+struct Vault {
+    // vault path -> notebook path
+    moved: BTreeMap<String, String>,
+    // attachment file name -> notebook path, for Obsidian's shortest-path links
+    attachments: BTreeMap<String, String>,
+}
+
+impl Vault {
+    fn attachment(&self, target: &str) -> Option<&String> {
+        self.moved
+            .get(target)
+            .filter(|p| !p.ends_with(".md"))
+            .or_else(|| {
+                let name = target.rsplit('/').next().unwrap_or(target);
+                self.attachments.get(name)
+            })
+    }
+
+    // Wiki-links to notes keep working through names and aliases, wiki-links to attachments
+    // and relative markdown links are pointed at the new locations.
+    fn rewrite(&self, content: &str, old: &str, new: &str) -> String {
+        let old_dir = Path::new(old).parent().unwrap_or(Path::new(""));
+        let mut out = String::new();
+        let mut in_fence = false;
+        for line in content.split_inclusive('\n') {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+                out.push_str(line);
+                continue;
+            }
+            if in_fence {
+                out.push_str(line);
+                continue;
+            }
+            let mut rest = line;
+            while !rest.is_empty() {
+                if let Some((inner, after)) = rest
+                    .strip_prefix("[[")
+                    .and_then(|r| r.find("]]").map(|e| (&r[..e], &r[e + 2..])))
+                {
+                    let target = inner.split(['|', '#']).next().unwrap_or("").trim();
+                    match self.attachment(target) {
+                        Some(path) => {
+                            let name = target.rsplit('/').next().unwrap_or(target);
+                            out.push_str(&format!(
+                                "[{}]({})",
+                                name,
+                                markdown_url(&relative(new, path))
+                            ));
+                        }
+                        None => out.push_str(&rest[..inner.len() + 4]),
+                    }
+                    rest = after;
+                } else if let Some((target, after)) = rest
+                    .strip_prefix("](")
+                    .and_then(|r| r.find(')').map(|e| (&r[..e], &r[e + 1..])))
+                {
+                    let path = target.trim().trim_start_matches('<').trim_end_matches('>');
+                    let (path, fragment) = match path.split_once('#') {
+                        Some((path, fragment)) => (path, format!("#{}", fragment)),
+                        None => (path, String::new()),
+                    };
+                    // Obsidian writes markdown links relative to the note or to the vault root.
+                    let decoded = path.replace("%20", " ");
+                    let moved = self
+                        .moved
+                        .get(&normalize(&old_dir.join(&decoded)))
+                        .or_else(|| self.moved.get(&normalize(Path::new(&decoded))));
+                    match moved {
+                        Some(moved) if !path.contains("://") && !path.is_empty() => {
+                            out.push_str(&format!(
+                                "]({}{})",
+                                markdown_url(&relative(new, moved)),
+                                fragment
+                            ));
+                        }
+                        _ => out.push_str(&rest[..target.len() + 3]),
+                    }
+                    rest = after;
+                } else {
+                    let c = rest.chars().next().unwrap_or_default();
+                    out.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        out
+    }
+}
+
+// Keys from an imported daily note's front matter are added to the diary entry's. Lists are
+// combined, other keys the entry already has are kept.
+fn merge_meta(into: &mut meta::NoteMeta, from: meta::NoteMeta) {
+    into.title = into.title.take().or(from.title);
+    into.created = into.created.take().or(from.created);
+    into.modified = into.modified.take().or(from.modified);
+    into.status = into.status.take().or(from.status);
+    for tag in from.tags {
+        if !into.tags.contains(&tag) {
+            into.tags.push(tag);
+        }
+    }
+    for alias in from.aliases {
+        if !into.aliases.contains(&alias) {
+            into.aliases.push(alias);
+        }
+    }
+    for (key, value) in from.extra {
+        into.extra.entry(key).or_insert(value);
+    }
+}
+
+fn with_daily_meta(
+    content: &str,
+    alias: &str,
+    imported: Option<(Format, meta::NoteMeta)>,
+    front_matter: &str,
+) -> Result<String, io::Error> {
+    let (imported_format, imported) = match imported {
+        Some((format, imported)) => (Some(format), imported),
+        None => (None, meta::NoteMeta::default()),
+    };
+    let (format, mut note_meta, before) = match meta::parse(content) {
+        Some((format, note_meta)) => (format, note_meta.clone(), Some(note_meta)),
+        None if meta::split(content).is_some() => return Ok(content.to_string()),
+        None => match Format::from_config(front_matter).or(imported_format) {
+            Some(format) => (format, meta::NoteMeta::default(), None),
+            None => return Ok(content.to_string()),
+        },
+    };
+    merge_meta(&mut note_meta, imported);
+    if !note_meta.aliases.iter().any(|a| a == alias) {
+        note_meta.aliases.push(alias.to_string());
+    }
+    if before.as_ref() == Some(&note_meta) {
+        return Ok(content.to_string());
+    }
+    meta::write(content, &note_meta, format)
+}
+
+pub fn obsidian(vault: String, config: Config) -> Result<(), io::Error> {
+    let root = PathBuf::from(config::expand_home(&vault));
+    if !root.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Vault does not exist.",
+        ));
+    }
+    let daily = read_json(&root.join(".obsidian/daily-notes.json"));
+    let daily_folder = daily["folder"]
+        .as_str()
+        .unwrap_or("")
+        .trim_matches('/')
+        .to_string();
+    let daily_format = chrono_format(
+        daily["format"]
+            .as_str()
+            .filter(|f| !f.is_empty())
+            .unwrap_or("YYYY-MM-DD"),
+    );
+
+    let mut files = Vec::new();
+    collect_files(&root, &root, &mut files)?;
+    files.sort();
+
+    let mut vault_map = Vault {
+        moved: BTreeMap::new(),
+        attachments: BTreeMap::new(),
+    };
+    let mut dates: BTreeMap<String, NaiveDate> = BTreeMap::new();
+    let mut projects: BTreeMap<String, String> = BTreeMap::new();
+    for file in &files {
+        if !file.ends_with(".md") {
+            let moved = format!("attachments/{}", file);
+            let name = file.rsplit('/').next().unwrap_or(file).to_string();
+            vault_map.attachments.entry(name).or_insert(moved.clone());
+            vault_map.moved.insert(file.clone(), moved);
+            continue;
+        }
+        let in_daily = daily_folder.is_empty() || file.starts_with(&format!("{}/", daily_folder));
+        let stem = file
+            .strip_prefix(&format!("{}/", daily_folder))
+            .unwrap_or(file)
+            .trim_end_matches(".md");
+        let date = NaiveDate::parse_from_str(stem, &daily_format)
+            .ok()
+            .filter(|_| in_daily);
+        let moved = match (date, file.split_once('/')) {
+            (Some(date), _) => {
+                dates.insert(file.clone(), date);
+                notes::daily_diary_path("", date)
+                    .trim_start_matches('/')
+                    .to_string()
+            }
+            (None, Some((folder, rest))) => {
+                let project = utils::slugify(folder);
+                projects.insert(project.clone(), folder.to_string());
+                project_note(&project, rest)
+            }
+            (None, None) => format!("quick-notes/{}", file),
+        };
+        vault_map.moved.insert(file.clone(), moved);
+    }
+
+    let note_dir = &config.core.note_dir;
+    let (mut imported, mut merged, mut skipped, mut attachments) = (0, 0, 0, 0);
+    for (file, moved) in &vault_map.moved {
+        let source = root.join(file);
+        let dest = format!("{}/{}", note_dir, moved);
+        if !moved.ends_with(".md") {
+            if utils::path_exists(&dest) {
+                skipped += 1;
+            } else {
+                utils::ensure_all_dirs(&dest)?;
+                fs::copy(&source, &dest)?;
+                attachments += 1;
+            }
+            continue;
+        }
+        let content = vault_map.rewrite(&fs::read_to_string(&source)?, file, moved);
+        if dates.contains_key(file) {
+            let stem = Path::new(file)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if utils::path_exists(&dest) {
+                let existing = fs::read_to_string(&dest)?;
+                // A body that is already in the entry was merged by an earlier import.
+                let body = meta::body(&content).trim();
+                let merged_content = if body.is_empty() || meta::body(&existing).contains(body) {
+                    existing.clone()
+                } else {
+                    format!("{}\n{}\n", existing.trim_end(), body)
+                };
+                let merged_content = with_daily_meta(
+                    &merged_content,
+                    &stem,
+                    meta::parse(&content),
+                    &config.core.front_matter,
+                )?;
+                if merged_content == existing {
+                    skipped += 1;
+                } else {
+                    fs::write(&dest, merged_content)?;
+                    merged += 1;
+                }
+                continue;
+            }
+            let content = with_daily_meta(&content, &stem, None, &config.core.front_matter)?;
+            utils::ensure_all_dirs(&dest)?;
+            fs::write(&dest, content)?;
+            imported += 1;
+            continue;
+        }
+        if utils::path_exists(&dest) {
+            skipped += 1;
+            continue;
+        }
+        utils::ensure_all_dirs(&dest)?;
+        fs::write(&dest, content)?;
+        imported += 1;
+    }
+
+    for (project, folder) in &projects {
+        let start = utils::project_start(note_dir, project);
+        if !utils::path_exists(&start) {
+            utils::save_file(&start, &meta::initial(folder, &config.core.front_matter)?)?;
+        }
+    }
+
+    println!(
+        "{} notes imported, {} merged into existing diary entries, {} attachments copied, {} skipped",
+        imported, merged, attachments, skipped
+    );
+    Ok(())
+}
+
+struct Entry {
+    at: NaiveDateTime,
+    title: String,
+    body: String,
+}
+
+// jrnl marks tags with @, grom with #.
+fn convert_tags(text: &str) -> String {
+    let mut out = String::new();
+    let mut in_fence = false;
+    for line in text.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        let mut previous = ' ';
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            let starts_tag = chars
+                .peek()
+                .is_some_and(|n| n.is_alphanumeric() || *n == '_');
+            if c == '@' && !in_fence && starts_tag && (previous.is_whitespace() || previous == '(')
+            {
+                out.push('#');
+            } else {
+                out.push(c);
+            }
+            previous = c;
+        }
+    }
+    out
+}
+
+fn jrnl_header(line: &str) -> Option<(NaiveDateTime, &str)> {
+    let formats = [
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %I:%M %p",
+        "%Y-%m-%d %I:%M:%S %p",
+    ];
+    let (stamp, rest) = match line.strip_prefix('[') {
+        Some(inner) => inner.split_once(']')?,
+        // Older journals have no brackets around the timestamp.
+        None => (line.get(..16)?, &line[16..]),
+    };
+    let at = formats
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(stamp.trim(), f).ok())?;
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('*').unwrap_or(rest);
+    Some((at, rest.trim()))
+}
+
+// jrnl stores the first sentence of an entry as its title and the rest as its body.
+fn jrnl_title(text: &str) -> (String, String) {
+    let end = text
+        .char_indices()
+        .find(|(i, c)| matches!(c, '.' | '?' | '!') && text[i + 1..].starts_with(' '))
+        .map(|(i, _)| i + 1);
+    match end {
+        Some(end) => (text[..end].to_string(), text[end..].trim().to_string()),
+        None => (text.to_string(), String::new()),
+    }
+}
+
+fn jrnl_entries(content: &str) -> Result<Vec<Entry>, io::Error> {
+    // `jrnl --export json` output, otherwise the plain journal file.
+    if content.trim_start().starts_with('{') {
+        let json: serde_json::Value = serde_json::from_str(content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let mut entries = Vec::new();
+        for entry in json["entries"].as_array().into_iter().flatten() {
+            let stamp = format!(
+                "{} {}",
+                entry["date"].as_str().unwrap_or(""),
+                entry["time"].as_str().unwrap_or("00:00")
+            );
+            let Ok(at) = NaiveDateTime::parse_from_str(&stamp, "%Y-%m-%d %H:%M") else {
+                continue;
+            };
+            entries.push(Entry {
+                at,
+                title: convert_tags(entry["title"].as_str().unwrap_or("")),
+                body: convert_tags(entry["body"].as_str().unwrap_or("")),
+            });
+        }
+        return Ok(entries);
+    }
+
+    let mut entries: Vec<Entry> = Vec::new();
+    for line in content.lines() {
+        match jrnl_header(line) {
+            Some((at, text)) => {
+                let (title, body) = jrnl_title(text);
+                entries.push(Entry {
+                    at,
+                    title: convert_tags(&title),
+                    body: convert_tags(&body),
+                });
+            }
+            None => {
+                if let Some(entry) = entries.last_mut() {
+                    entry.body.push('\n');
+                    entry.body.push_str(&convert_tags(line));
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+// Day One escapes punctuation that has no meaning in the middle of a line.
+fn unescape_dayone(text: &str) -> String {
+    let mut out = String::new();
+    for line in text.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let escaped = chars
+                .peek()
+                .is_some_and(|(_, n)| matches!(n, '.' | '-' | '+' | '!' | '(' | ')'));
+            // Escapes that keep a line from turning into a list are left alone.
+            let leading = line[indent..i].chars().all(|c| c.is_ascii_digit());
+            if c == '\\' && escaped && !leading {
+                continue;
+            }
+            out.push(c);
+        }
+    }
+    out
+}
+
+// dayone-moment identifier -> (exported file, notebook path)
+type Photos = BTreeMap<String, (PathBuf, String)>;
+
+fn dayone_entries(export: &Path, note_dir: &str) -> Result<(Vec<Entry>, Photos), io::Error> {
+    let json = read_json(export);
+    let Some(items) = json["entries"].as_array() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a Day One JSON export.",
+        ));
+    };
+    let photos_dir = export.parent().unwrap_or(Path::new("")).join("photos");
+    let mut entries = Vec::new();
+    let mut photos = BTreeMap::new();
+    for item in items {
+        let Some(at) = item["creationDate"]
+            .as_str()
+            .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+        else {
+            continue;
+        };
+        for photo in item["photos"].as_array().into_iter().flatten() {
+            let (Some(id), Some(md5)) = (photo["identifier"].as_str(), photo["md5"].as_str())
+            else {
+                continue;
+            };
+            let file = format!("{}.{}", md5, photo["type"].as_str().unwrap_or("jpeg"));
+            if photos_dir.join(&file).is_file() {
+                photos.insert(
+                    id.to_string(),
+                    (
+                        photos_dir.join(&file),
+                        format!("{}/attachments/dayone/{}", note_dir, file),
+                    ),
+                );
+            }
+        }
+        let text = unescape_dayone(item["text"].as_str().unwrap_or(""));
+        let (title, body) = match text.split_once('\n') {
+            Some((first, rest)) if first.starts_with('#') => {
+                (first.trim_start_matches('#').trim(), rest)
+            }
+            _ => ("", text.as_str()),
+        };
+        let mut body = body.trim().to_string();
+        let tags: Vec<String> = item["tags"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|t| t.as_str())
+            .map(|t| format!("#{}", utils::slugify(t)))
+            .collect();
+        if !tags.is_empty() {
+            body = format!("{}\n\n{}", body, tags.join(" "));
+        }
+        entries.push(Entry {
+            at: at.with_timezone(&Local).naive_local(),
+            title: title.to_string(),
+            body,
+        });
+    }
+    Ok((entries, photos))
+}
+
+fn entry_heading(entry: &Entry) -> String {
+    format!("## {} {}", entry.at.format("%H:%M"), entry.title)
+        .trim_end()
+        .to_string()
+}
+
+// Offset of the first timestamped section that comes after `at`, to keep merged days in order.
+fn later_section(content: &str, at: NaiveDateTime) -> Option<usize> {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let time = line
+            .strip_prefix("## ")
+            .and_then(|rest| rest.get(..5))
+            .and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok());
+        if time.is_some_and(|t| t > at.time()) {
+            return Some(offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+// Writes the entries into daily diary notes, one section per entry. Entries whose heading
+// is already in the note are skipped, so importing the same journal twice is harmless.
+fn write_entries(
+    mut entries: Vec<Entry>,
+    photos: &Photos,
+    config: &Config,
+) -> Result<(), io::Error> {
+    entries.sort_by_key(|e| e.at);
+    let mut days: BTreeMap<NaiveDate, Vec<Entry>> = BTreeMap::new();
+    for entry in entries {
+        days.entry(entry.at.date()).or_default().push(entry);
+    }
+
+    let (mut imported, mut skipped, mut notes_written) = (0, 0, 0);
+    for (date, entries) in &days {
+        let file = notes::daily_diary_path(&config.core.note_dir, *date);
+        let rel = file
+            .strip_prefix(&format!("{}/", config.core.note_dir))
+            .unwrap_or(&file)
+            .to_string();
+        let mut content = if utils::path_exists(&file) {
+            fs::read_to_string(&file)?
+        } else {
+            let title = notes::diary_title(DiaryPeriod::Daily, *date);
+            match Format::from_config(&config.core.front_matter) {
+                Some(format) => meta::NoteMeta {
+                    title: Some(title),
+                    created: Some(entries[0].at.format("%Y-%m-%dT%H:%M:%S").to_string()),
+                    ..Default::default()
+                }
+                .render(format)?,
+                None => String::new(),
+            }
+        };
+        let before = content.clone();
+        for entry in entries {
+            let heading = entry_heading(entry);
+            if content.lines().any(|l| l.trim_end() == heading) {
+                skipped += 1;
+                continue;
+            }
+            let mut body = entry.body.trim().to_string();
+            for (id, (source, dest)) in photos {
+                let link = format!("dayone-moment://{}", id);
+                if body.contains(&link) {
+                    let target = dest
+                        .strip_prefix(&format!("{}/", config.core.note_dir))
+                        .unwrap_or(dest);
+                    body = body.replace(&link, &relative(&rel, target));
+                    if !utils::path_exists(dest) {
+                        utils::ensure_all_dirs(dest)?;
+                        fs::copy(source, dest)?;
+                    }
+                }
+            }
+            let mut section = format!("{}\n", heading);
+            if !body.is_empty() {
+                section = format!("{}{}\n", section, body);
+            }
+            content = match later_section(&content, entry.at) {
+                Some(offset) => {
+                    format!("{}{}\n{}", &content[..offset], section, &content[offset..])
+                }
+                None => {
+                    let mut text = content.trim_end().to_string();
+                    if !text.is_empty() {
+                        text.push_str(if meta::body(&text).trim().is_empty() {
+                            "\n"
+                        } else {
+                            "\n\n"
+                        });
+                    }
+                    text + &section
+                }
+            };
+            imported += 1;
+        }
+        if content != before {
+            utils::ensure_all_dirs(&file)?;
+            fs::write(&file, content)?;
+            notes_written += 1;
+        }
+    }
+    println!(
+        "{} entries imported into {} diary notes, {} already present",
+        imported, notes_written, skipped
+    );
+    Ok(())
+}
+
+pub fn jrnl(file: String, config: Config) -> Result<(), io::Error> {
+    let content = fs::read_to_string(config::expand_home(&file))?;
+    let entries = jrnl_entries(&content)?;
+    if entries.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "No journal entries found.",
+        ));
+    }
+    write_entries(entries, &BTreeMap::new(), &config)
+}
+
+pub fn dayone(export: String, config: Config) -> Result<(), io::Error> {
+    let export = PathBuf::from(config::expand_home(&export));
+    if !export.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Export does not exist.",
+        ));
+    }
+    let (entries, photos) = dayone_entries(&export, &config.core.note_dir)?;
+    write_entries(entries, &photos, &config)
+}
+
+// A note converted from another application, with paths relative to the notebook root.
+struct Converted {
+    path: String,
+    content: String,
+    assets: Vec<(String, Vec<u8>)>,
+    warnings: Vec<String>,
+}
+
+// Makes `path` unique among the paths handed out during this import.
+fn unique(path: String, used: &mut BTreeSet<String>) -> String {
+    let (stem, extension) = match path.rsplit_once('.') {
+        Some((stem, extension)) if !extension.contains('/') => {
+            (stem.to_string(), format!(".{}", extension))
+        }
+        _ => (path.clone(), String::new()),
+    };
+    let mut candidate = path;
+    let mut n = 1;
+    while used.contains(&candidate) {
+        candidate = format!("{}-{}{}", stem, n, extension);
+        n += 1;
+    }
+    used.insert(candidate.clone());
+    candidate
+}
+
+// start.md is the project's own note in grom, so imported notes of that name are renamed.
+fn project_note(project: &str, file: &str) -> String {
+    let file = if file == "start.md" {
+        "start-1.md"
+    } else {
+        file
+    };
+    format!("projects/{}/{}", project, file)
+}
+
+fn file_name(name: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => {
+            format!("{}.{}", utils::slugify(stem), utils::slugify(extension))
+        }
+        _ => utils::slugify(name),
+    }
+}
+
+fn with_meta(
+    body: &str,
+    note_meta: meta::NoteMeta,
+    front_matter: &str,
+) -> Result<String, io::Error> {
+    match Format::from_config(front_matter) {
+        Some(format) => Ok(format!("{}{}", note_meta.render(format)?, body)),
+        // Without front matter the tags are kept inline.
+        None if !note_meta.tags.is_empty() => {
+            let tags: Vec<String> = note_meta.tags.iter().map(|t| format!("#{}", t)).collect();
+            Ok(format!("{}\n{}\n", body.trim_end(), tags.join(" ")))
+        }
+        None => Ok(body.to_string()),
+    }
+}
+
+fn write_converted(
+    notes: Vec<Converted>,
+    projects: &BTreeMap<String, String>,
+    source: &str,
+    report: Option<String>,
+    config: &Config,
+) -> Result<(), io::Error> {
+    let note_dir = &config.core.note_dir;
+    let (mut imported, mut attachments, mut skipped) = (0, 0, 0);
+    let mut warnings = Vec::new();
+    for note in notes {
+        // Assets may be shared with later notes, so they are copied even for skipped notes.
+        for (asset, data) in &note.assets {
+            let dest = format!("{}/{}", note_dir, asset);
+            if !utils::path_exists(&dest) {
+                utils::ensure_all_dirs(&dest)?;
+                fs::write(&dest, data)?;
+                attachments += 1;
+            }
+        }
+        let dest = format!("{}/{}", note_dir, note.path);
+        if !note.content.is_empty() && utils::path_exists(&dest) {
+            skipped += 1;
+            continue;
+        }
+        for warning in &note.warnings {
+            warnings.push(format!("{}: {}", note.path, warning));
+        }
+        if note.content.is_empty() {
+            continue;
+        }
+        utils::ensure_all_dirs(&dest)?;
+        fs::write(&dest, &note.content)?;
+        imported += 1;
+    }
+    for (project, name) in projects {
+        let start = utils::project_start(note_dir, project);
+        if !utils::path_exists(&start) {
+            utils::save_file(&start, &meta::initial(name, &config.core.front_matter)?)?;
+        }
+    }
+
+    print!(
+        "{} notes imported, {} attachments copied, {} skipped",
+        imported, attachments, skipped
+    );
+    if warnings.is_empty() {
+        println!();
+        return Ok(());
+    }
+    let report = report
+        .map(|r| config::expand_home(&r))
+        .unwrap_or_else(|| format!("{}/import-report.txt", note_dir));
+    fs::write(
+        &report,
+        format!(
+            "Conversion warnings for {}\n\n{}\n",
+            source,
+            warnings.join("\n")
+        ),
+    )?;
+    println!(", {} warnings written to {}", warnings.len(), report);
+    Ok(())
+}
+
+pub fn enex(file: String, report: Option<String>, config: Config) -> Result<(), io::Error> {
+    let path = PathBuf::from(config::expand_home(&file));
+    let notes = enex::parse(&fs::read_to_string(&path)?)?;
+    if notes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "No notes found in the export.",
+        ));
+    }
+    // Evernote exports one notebook per file, named after the notebook.
+    let notebook = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let project = utils::slugify(&notebook);
+    let projects = BTreeMap::from([(project.clone(), notebook)]);
+
+    let mut used = BTreeSet::new();
+    let mut converted = Vec::new();
+    for note in notes {
+        let title = if note.title.is_empty() {
+            "Untitled".to_string()
+        } else {
+            note.title.clone()
+        };
+        let path = unique(
+            project_note(&project, &format!("{}.md", utils::slugify(&title))),
+            &mut used,
+        );
+        let mut assets: Vec<(String, Vec<u8>)> = Vec::new();
+        let mut linked: BTreeMap<String, String> = BTreeMap::new();
+        let mut media = |hash: &str, mime: &str| {
+            let resource = note.resources.iter().find(|r| r.hash == hash)?;
+            let name = resource.file_name.clone().unwrap_or_else(|| {
+                let extension = mime.rsplit('/').next().unwrap_or("bin");
+                format!("{}.{}", hash, extension)
+            });
+            let asset = linked
+                .entry(hash.to_string())
+                .or_insert_with(|| {
+                    let asset = unique(
+                        format!("projects/{}/assets/{}", project, file_name(&name)),
+                        &mut used,
+                    );
+                    assets.push((asset.clone(), resource.data.clone()));
+                    asset
+                })
+                .clone();
+            let image = if mime.starts_with("image/") { "!" } else { "" };
+            Some(format!("{}[{}]({})", image, name, relative(&path, &asset)))
+        };
+        let conversion = enml::to_markdown(&note.content, &mut media);
+        let mut extra = BTreeMap::new();
+        if let Some(url) = &note.source_url {
+            extra.insert("source".to_string(), serde_json::Value::from(url.clone()));
+        }
+        let note_meta = meta::NoteMeta {
+            title: Some(title),
+            created: note.created.clone(),
+            modified: note.updated.clone(),
+            tags: note.tags.iter().map(|t| utils::slugify(t)).collect(),
+            extra,
+            ..Default::default()
+        };
+        converted.push(Converted {
+            content: with_meta(&conversion.markdown, note_meta, &config.core.front_matter)?,
+            path,
+            assets,
+            warnings: conversion.warnings,
+        });
+    }
+    write_converted(converted, &projects, &file, report, &config)
+}
+
+// Joplin links to notes and resources with `:/` followed by the item id.
+fn joplin_links(
+    body: &str,
+    mut target: impl FnMut(&str) -> Option<String>,
+    warnings: &mut Vec<String>,
+) -> String {
+    let mut out = String::new();
+    let mut rest = body;
+    while let Some(start) = rest.find(":/") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let id = after
+            .get(..32)
+            .filter(|id| id.chars().all(|c| c.is_ascii_hexdigit()));
+        match id {
+            Some(id) => {
+                match target(id) {
+                    Some(path) => out.push_str(&path),
+                    None => {
+                        warnings.push(format!("link to missing item {} left unchanged", id));
+                        out.push_str(&rest[start..start + 34]);
+                    }
+                }
+                rest = &after[32..];
+            }
+            None => {
+                out.push_str(":/");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn joplin(file: String, report: Option<String>, config: Config) -> Result<(), io::Error> {
+    let archive = joplin::read(Path::new(&config::expand_home(&file)))?;
+    let items: BTreeMap<&str, &joplin::Item> =
+        archive.items.iter().map(|i| (i.id.as_str(), i)).collect();
+    let mut tags: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for item in archive.items.iter().filter(|i| i.kind == joplin::NOTE_TAG) {
+        if let Some(tag) = items.get(item.prop("tag_id")) {
+            tags.entry(item.prop("note_id"))
+                .or_default()
+                .push(utils::slugify(&tag.title));
+        }
+    }
+
+    // Top-level notebooks become projects, nested notebooks folders inside them.
+    let mut used = BTreeSet::new();
+    let mut projects = BTreeMap::new();
+    let mut paths: BTreeMap<&str, String> = BTreeMap::new();
+    let notes: Vec<&joplin::Item> = archive
+        .items
+        .iter()
+        .filter(|i| i.kind == joplin::NOTE)
+        .collect();
+    for note in &notes {
+        let mut folders = Vec::new();
+        let mut parent = note.prop("parent_id");
+        while let Some(folder) = items.get(parent).filter(|f| f.kind == joplin::FOLDER) {
+            if folders.len() > 32 {
+                break;
+            }
+            folders.insert(0, folder.title.clone());
+            parent = folder.prop("parent_id");
+        }
+        let title = if note.title.is_empty() {
+            "untitled".to_string()
+        } else {
+            utils::slugify(&note.title)
+        };
+        let path = match folders.split_first() {
+            Some((notebook, nested)) => {
+                let project = utils::slugify(notebook);
+                projects.insert(project.clone(), notebook.clone());
+                let mut file = format!("{}.md", title);
+                for folder in nested.iter().rev() {
+                    file = format!("{}/{}", utils::slugify(folder), file);
+                }
+                project_note(&project, &file)
+            }
+            None => format!("quick-notes/{}.md", title),
+        };
+        paths.insert(&note.id, unique(path, &mut used));
+    }
+
+    // Resources are copied once per asset folder, however many notes link to them.
+    let mut linked: BTreeMap<String, String> = BTreeMap::new();
+    let mut converted = Vec::new();
+    for note in notes {
+        let path = paths[note.id.as_str()].clone();
+        let mut warnings = Vec::new();
+        if note.prop("encryption_applied") == "1" {
+            warnings.push("encrypted note skipped".to_string());
+        } else if note.prop("is_conflict") == "1" {
+            warnings.push("conflicting copy of a note skipped".to_string());
+        }
+        if !warnings.is_empty() {
+            converted.push(Converted {
+                path,
+                content: String::new(),
+                assets: Vec::new(),
+                warnings,
+            });
+            continue;
+        }
+        // Notes saved by the web clipper are HTML.
+        let body = if note.prop("markup_language") == "2" {
+            let conversion = enml::to_markdown(&note.body, &mut |_, _| None);
+            warnings.extend(conversion.warnings);
+            conversion.markdown
+        } else {
+            format!("{}\n", note.body)
+        };
+        let asset_dir = match path.strip_prefix("projects/") {
+            Some(rest) => format!("projects/{}/assets", rest.split('/').next().unwrap_or("")),
+            None => "quick-notes/assets".to_string(),
+        };
+        let mut assets = Vec::new();
+        let body = joplin_links(
+            &body,
+            |id| {
+                if let Some(target) = paths.get(id) {
+                    return Some(relative(&path, target));
+                }
+                let resource = items.get(id).filter(|i| i.kind == joplin::RESOURCE)?;
+                let data = archive.resources.get(id)?;
+                let name = if resource.title.is_empty() {
+                    format!("{}.{}", id, resource.prop("file_extension"))
+                } else {
+                    resource.title.clone()
+                };
+                let key = format!("{}/{}", asset_dir, id);
+                let asset = linked.entry(key).or_insert_with(|| {
+                    let asset = unique(format!("{}/{}", asset_dir, file_name(&name)), &mut used);
+                    assets.push((asset.clone(), data.clone()));
+                    asset
+                });
+                Some(relative(&path, asset))
+            },
+            &mut warnings,
+        );
+        let status = match (note.prop("is_todo"), note.prop("todo_completed")) {
+            ("1", "0" | "") => Some("todo".to_string()),
+            ("1", _) => Some("done".to_string()),
+            _ => None,
+        };
+        let mut extra = BTreeMap::new();
+        if !note.prop("source_url").is_empty() {
+            extra.insert(
+                "source".to_string(),
+                serde_json::Value::from(note.prop("source_url")),
+            );
+        }
+        let note_meta = meta::NoteMeta {
+            title: Some(note.title.clone()),
+            created: note
+                .timestamp("user_created_time")
+                .or_else(|| note.timestamp("created_time")),
+            modified: note
+                .timestamp("user_updated_time")
+                .or_else(|| note.timestamp("updated_time")),
+            tags: tags.remove(note.id.as_str()).unwrap_or_default(),
+            status,
+            extra,
+            ..Default::default()
+        };
+        converted.push(Converted {
+            content: with_meta(&body, note_meta, &config.core.front_matter)?,
+            path,
+            assets,
+            warnings,
+        });
+    }
+    write_converted(converted, &projects, &file, report, &config)
+}
@@ -0,0 +1,130 @@
+use crate::core::config::{self, Config};
+use crate::core::ics::{self, Event};
+use crate::core::notes;
+use crate::core::{meta, tasks, utils};
+use chrono::{Local, NaiveDate};
+use std::path::Path;
+use std::{fs, io};
+
+const DEFAULT_TEMPLATE: &str = "# {{title}}
+
+- Date: {{date}} {{time}}-{{end}}
+- Location: {{location}}
+- Attendees: {{attendees}}
+
+## Agenda
+
+## Notes
+
+## Action items
+";
+
+fn template(config: &Config) -> Result<String, io::Error> {
+    let path = match &config.meetings.template {
+        Some(path) => config::expand_home(path),
+        None => config::config_dir() + "/templates/meeting.md",
+    };
+    if utils::path_exists(&path) {
+        fs::read_to_string(path)
+    } else if config.meetings.template.is_some() {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Meeting template does not exist.",
+        ))
+    } else {
+        Ok(DEFAULT_TEMPLATE.to_string())
+    }
+}
+
+fn render(template: &str, event: &Event) -> String {
+    let time = |t: chrono::NaiveDateTime| t.format("%H:%M").to_string();
+    template
+        .replace("{{title}}", &event.summary)
+        .replace("{{date}}", &event.start.format("%Y-%m-%d").to_string())
+        .replace("{{time}}", &time(event.start))
+        .replace("{{end}}", &event.end.map(time).unwrap_or_default())
+        .replace("{{location}}", event.location.as_deref().unwrap_or(""))
+        .replace("{{attendees}}", &event.attendees.join(", "))
+}
+
+fn project_for(event: &Event, config: &Config) -> Option<String> {
+    let summary = event.summary.to_lowercase();
+    config
+        .meetings
+        .keywords
+        .iter()
+        .find(|(keyword, _)| summary.contains(&keyword.to_lowercase()))
+        .map(|(_, project)| project.clone())
+}
+
+fn link_from_diary(
+    day: NaiveDate,
+    event: &Event,
+    stem: &str,
+    config: &Config,
+) -> Result<(), io::Error> {
+    let diary = notes::daily_diary_path(&config.core.note_dir, day);
+    if !utils::path_exists(&diary) {
+        utils::ensure_all_dirs(&diary)?;
+        let title = day.format("%A, %B %d %Y").to_string();
+        utils::save_file(&diary, &meta::initial(&title, &config.core.front_matter)?)?;
+    }
+    let content = fs::read_to_string(&diary)?;
+    let link = format!("[[{}|{}]]", stem, event.summary);
+    if content.contains(&format!("[[{}|", stem)) || content.contains(&format!("[[{}]]", stem)) {
+        return Ok(());
+    }
+    let mut updated = content;
+    if !updated.is_empty() && !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    let line = if event.all_day {
+        format!("- {}\n", link)
+    } else {
+        format!("- {} {}\n", event.start.format("%H:%M"), link)
+    };
+    updated.push_str(&line);
+    tasks::write_note(Path::new(&diary), &updated)
+}
+
+pub fn meetings(ics_file: String, date: String, config: Config) -> Result<(), io::Error> {
+    let day = tasks::parse_relative_date(&date, Local::now().date_naive())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid date."))?;
+    let content = fs::read_to_string(config::expand_home(&ics_file))?;
+    let (mut events, warnings) = ics::events_on(&ics::parse_events(&content), day);
+    for warning in warnings {
+        println!("{}", warning);
+    }
+    events.sort_by_key(|e| e.start);
+    if events.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No meetings on that day.",
+        ));
+    }
+    let template = template(&config)?;
+
+    for event in &events {
+        let stem = format!(
+            "{}-{}",
+            event.start.format("%Y-%m-%d-%H%M"),
+            utils::slugify(&event.summary)
+        );
+        let path = match project_for(event, &config) {
+            Some(project) => format!(
+                "{}/projects/{}/meetings/{}.md",
+                config.core.note_dir, project, stem
+            ),
+            None => format!("{}/meetings/{}.md", config.core.note_dir, stem),
+        };
+        if !utils::path_exists(&path) {
+            let mut content = meta::initial(&event.summary, &config.core.front_matter)?;
+            content.push_str(&render(&template, event));
+            utils::ensure_all_dirs(&path)?;
+            utils::save_file(&path, &content)?;
+        }
+        link_from_diary(day, event, &stem, &config)?;
+        println!("{}", path);
+    }
+    Ok(())
+}
@@ -1,20 +1,21 @@
 use crate::core::config::Config;
+use crate::core::meta;
 use crate::core::utils;
 use std::io;
 
 pub fn create(project_name: String, config: Config) -> Result<(), std::io::Error> {
-    let path = format!("{}/projects/{}/.start.md", config.core.note_dir, project_name);
+    let path = utils::project_start(&config.core.note_dir, &project_name);
 
     if utils::path_exists(&path) {
         Err(io::Error::new(io::ErrorKind::AlreadyExists, "Project already exists."))
     } else {
         utils::ensure_all_dirs(&path)?;
-        utils::save_file(&path)
+        utils::save_file(&path, &meta::initial(&project_name, &config.core.front_matter)?)
     }
 }
 
 pub fn open(project_name: String, config: Config) -> Result<(), std::io::Error> {
-    let project_base = format!("{}/projects/{}/start.md", config.core.note_dir, project_name);
+    let project_base = utils::project_start(&config.core.note_dir, &project_name);
     if utils::path_exists(&project_base) {
         utils::open_file(&config.core.editor, &project_base)
     } else {
@@ -0,0 +1,125 @@
+use crate::core::config::Config;
+use crate::core::html;
+use crate::core::markdown;
+use crate::core::notes::{Note, Notebook};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::{fs, io};
+
+fn is_published(note: &Note) -> bool {
+    let flagged = match note.meta.extra.get("publish") {
+        Some(serde_json::Value::Bool(publish)) => *publish,
+        Some(serde_json::Value::String(publish)) => publish.eq_ignore_ascii_case("true"),
+        _ => false,
+    };
+    flagged || note.tags().iter().any(|t| t == "public")
+}
+
+fn copy(rel_path: &str, out_dir: &Path, content: &[u8]) -> Result<(), io::Error> {
+    let dest = out_dir.join(rel_path);
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(dest, content)
+}
+
+// Markdown bundles keep the notebook layout, with wiki-links turned into relative links.
+fn bundle(notebook: &Notebook, published: &[usize], out_dir: &Path) -> Result<(), io::Error> {
+    let selected: BTreeSet<usize> = published.iter().copied().collect();
+    let mut assets = BTreeSet::new();
+    for i in published {
+        let note = &notebook.notes[*i];
+        let prefix = html::root(&note.rel_path);
+        let dir = note.path.parent().unwrap_or(&notebook.root);
+        // Only links and embeds of files that exist are kept, so none point at private notes.
+        let content = markdown::map_links(&note.content, &mut |link| match notebook
+            .resolve(*i, link)
+        {
+            Some(target) if selected.contains(&target) => {
+                Some(format!("{}{}", prefix, notebook.notes[target].rel_path))
+            }
+            Some(_) => None,
+            None if !link.target.ends_with(".md") && dir.join(&link.target).is_file() => {
+                Some(link.target.clone())
+            }
+            None => None,
+        });
+        copy(&note.rel_path, out_dir, content.as_bytes())?;
+        assets.extend(html::render(notebook, *i, &|_| None, None).assets);
+    }
+    for asset in assets {
+        let source = notebook.root.join(&asset);
+        if source.is_file() && !asset.ends_with(".md") {
+            copy(&asset, out_dir, &fs::read(source)?)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn publish(out_dir: String, format: String, config: Config) -> Result<(), io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let published: Vec<usize> = (0..notebook.notes.len())
+        .filter(|i| is_published(&notebook.notes[*i]))
+        .collect();
+    if published.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No published notes found.",
+        ));
+    }
+    match format.as_str() {
+        "html" => {
+            html::write_site(&notebook, &published, Path::new(&out_dir), "Notes")?;
+        }
+        "markdown" => bundle(&notebook, &published, Path::new(&out_dir))?,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unknown output format.",
+            ))
+        }
+    }
+    println!("{} notes published to {}", published.len(), out_dir);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::TempNotebook;
+
+    #[test]
+    fn private_notes_leave_no_trace() {
+        let temp = TempNotebook::new(&[
+            (
+                "public.md",
+                "---\ntags: [public]\n---\n# Public\n\n\
+                 See [[secret]], [[secret|the plan]] and [the [draft] plan](secret.md).\n\
+                 Also [the notes][plans], [plans] and <file:secret.md>.\n\
+                 Read [the other one](other.md \"Other\") and [[other#Intro]].\n\
+                 ![[secret]] ![the secret](secret.md) ![[photo.png]] ![chart](chart.png)\n\n\
+                 [plans]: secret.md \"Plans\"\n[site]: https://example.com\n",
+            ),
+            ("other.md", "# Other\n\n#public\n"),
+            ("secret.md", "# Secret\n"),
+            ("photo.png", "png"),
+        ]);
+        let notebook = temp.load();
+        let published: Vec<usize> = (0..notebook.notes.len())
+            .filter(|i| is_published(&notebook.notes[*i]))
+            .collect();
+        let out = temp.path().join("out");
+        bundle(&notebook, &published, &out).unwrap();
+
+        let public = fs::read_to_string(out.join("public.md")).unwrap();
+        assert!(!out.join("secret.md").exists());
+        assert!(!public.contains("secret.md"), "{public}");
+        assert!(public.contains("See secret, the plan and the [draft] plan."));
+        assert!(public.contains("Also the notes, plans and ."));
+        assert!(public.contains("[the other one](other.md \"Other\")"));
+        assert!(public.contains("[other#Intro](other.md#Intro)"));
+        assert!(public.contains("secret the secret ![photo.png](photo.png) chart\n"));
+        assert!(out.join("photo.png").exists());
+        assert!(public.ends_with("\n\n[site]: https://example.com\n"));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::core::config::Config;
+use crate::core::notes::{Note, Notebook};
+use crate::core::{query, utils};
+use serde::Serialize;
+use std::io;
+
+#[derive(Serialize)]
+struct Row {
+    path: String,
+    title: String,
+    kind: String,
+    project: Option<String>,
+    date: Option<String>,
+    created: Option<String>,
+    modified: Option<String>,
+    status: Option<String>,
+    tags: Vec<String>,
+}
+
+fn row(note: &Note) -> Row {
+    Row {
+        path: note.rel_path.clone(),
+        title: note.title(),
+        kind: note.kind.as_str().to_string(),
+        project: note.project.clone(),
+        date: note.date.map(|d| d.format("%Y-%m-%d").to_string()),
+        created: note
+            .created()
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string()),
+        modified: note
+            .modified()
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string()),
+        status: note.meta.status.clone(),
+        tags: note.tags(),
+    }
+}
+
+fn print_table(rows: &[Row]) {
+    let table: Vec<Vec<String>> = rows
+        .iter()
+        .map(|r| {
+            vec![
+                r.title.clone(),
+                r.kind.clone(),
+                r.project.clone().unwrap_or_default(),
+                r.modified.clone().unwrap_or_default(),
+                r.tags.join(","),
+                r.path.clone(),
+            ]
+        })
+        .collect();
+    utils::print_table(
+        &["TITLE", "KIND", "PROJECT", "MODIFIED", "TAGS", "PATH"],
+        &table,
+    );
+}
+
+pub fn query(expr: String, format: String, config: Config) -> Result<(), io::Error> {
+    let query = query::parse(&expr)?;
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let results = query.run(&notebook);
+    let rows: Vec<Row> = results.iter().map(|n| row(n)).collect();
+
+    match format.as_str() {
+        "table" => print_table(&rows),
+        "json" => println!("{}", serde_json::to_string_pretty(&rows)?),
+        "picker" => {
+            if results.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "No notes found."));
+            }
+            cliclack::intro(console::style(" Grom ").on_cyan().black()).unwrap();
+            let items: Vec<_> = results
+                .iter()
+                .map(|n| {
+                    (
+                        n.path.to_string_lossy().to_string(),
+                        n.title(),
+                        n.rel_path.clone(),
+                    )
+                })
+                .collect();
+            let path = cliclack::select("Select a Note".to_string())
+                .items(&items)
+                .interact()
+                .map_err(|_| io::Error::other("Error selecting note"))?;
+            utils::open_file(&config.core.editor, &path)?;
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unknown output format.",
+            ))
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use crate::core::config::Config;
+use crate::core::meta;
 use crate::core::utils;
 
 pub fn quick_note(note_name: &str, config: Config) -> Result<(),std::io::Error> {
@@ -7,6 +8,6 @@ pub fn quick_note(note_name: &str, config: Config) -> Result<(),std::io::Error>
         return utils::open_file(&config.core.editor, &filepath)
     }
     utils::ensure_all_dirs(&filepath)?;
-    utils::save_file(&filepath)?;
+    utils::save_file(&filepath, &meta::initial(note_name, &config.core.front_matter)?)?;
     utils::open_file(&config.core.editor, &filepath)
 }
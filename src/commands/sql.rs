@@ -0,0 +1,42 @@
+use crate::core::catalog;
+use crate::core::config::Config;
+use crate::core::utils;
+use std::io;
+
+pub fn sql(query: String, format: String, config: Config) -> Result<(), io::Error> {
+    let result = catalog::query(&config.core.note_dir, &query)?;
+    match format.as_str() {
+        "table" => {
+            let rows: Vec<Vec<String>> = result
+                .rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|v| match v {
+                            serde_json::Value::Null => String::new(),
+                            serde_json::Value::String(s) => s.replace('\n', " "),
+                            v => v.to_string(),
+                        })
+                        .collect()
+                })
+                .collect();
+            let header: Vec<&str> = result.columns.iter().map(|c| c.as_str()).collect();
+            utils::print_table(&header, &rows);
+        }
+        "json" => {
+            let records: Vec<serde_json::Map<String, serde_json::Value>> = result
+                .rows
+                .into_iter()
+                .map(|row| result.columns.iter().cloned().zip(row).collect())
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&records)?);
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unknown output format.",
+            ))
+        }
+    }
+    Ok(())
+}
@@ -1,16 +1,45 @@
 use std::io;
 
 use crate::core::config::Config;
-use crate::core::git;
+use crate::core::git::{self, PullOutcome};
 
 pub fn init(remote: String, config: Config) -> Result<(), io::Error> {
-    git::init_sync(config.core.note_dir.clone(), remote)
+    git::init_sync(
+        config.core.note_dir.clone(),
+        remote,
+        config.sync.remote,
+        config.sync.branch,
+    )
 }
 
 pub fn push(message: String, config: Config) -> Result<(), io::Error> {
-    git::push_changes(config.core.note_dir, message)
+    git::push_changes(
+        config.core.note_dir,
+        message,
+        config.sync.remote,
+        config.sync.branch,
+    )
 }
 
-pub fn pull(config: Config) -> Result<(), io::Error> {
-    git::pull_changes(config.core.note_dir)
+pub fn pull(rebase: bool, config: Config) -> Result<(), io::Error> {
+    let outcome = git::pull_changes(
+        config.core.note_dir,
+        config.sync.remote,
+        config.sync.branch,
+        rebase,
+    )?;
+    match outcome {
+        PullOutcome::UpToDate => println!("Already up to date."),
+        PullOutcome::FastForward => println!("Fast-forwarded to the remote changes."),
+        PullOutcome::Merged => println!("Merged the remote changes."),
+        PullOutcome::Rebased => println!("Rebased local changes onto the remote changes."),
+        PullOutcome::Conflicts(paths) => {
+            println!("Conflicts in {} files:", paths.len());
+            for path in paths {
+                println!("  {}", path);
+            }
+            println!("Resolve the conflict markers, then run grom sync push.");
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,497 @@
+use crate::core::config::Config;
+use crate::core::meta;
+use crate::core::notes::Notebook;
+use crate::core::tasks::{self, Task};
+use crate::core::{taskwarrior, todotxt, utils};
+use chrono::{Days, Local, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+pub struct Filter {
+    pub project: Option<String>,
+    pub tag: Option<String>,
+    pub due: Option<String>,
+    pub status: String,
+}
+
+#[derive(Serialize)]
+struct Row {
+    id: String,
+    path: String,
+    line: usize,
+    done: bool,
+    description: String,
+    priority: Option<char>,
+    due: Option<String>,
+    project: Option<String>,
+    people: Vec<String>,
+    projects: Vec<String>,
+    tags: Vec<String>,
+    annotations: BTreeMap<String, String>,
+}
+
+fn row(task: &Task) -> Row {
+    Row {
+        id: task.id.clone(),
+        path: task.rel_path.clone(),
+        line: task.line,
+        done: task.done,
+        description: task.description.clone(),
+        priority: task.priority,
+        due: task.due.map(|d| d.format("%Y-%m-%d").to_string()),
+        project: task.project.clone(),
+        people: task.people.clone(),
+        projects: task.projects.clone(),
+        tags: task.tags.clone(),
+        annotations: task.annotations.clone(),
+    }
+}
+
+fn due_matches(task: &Task, window: &str, today: NaiveDate) -> Result<bool, io::Error> {
+    let within = |days: u64| {
+        task.due
+            .is_some_and(|d| d <= today.checked_add_days(Days::new(days)).unwrap_or(today))
+    };
+    Ok(match window {
+        "none" => task.due.is_none(),
+        "overdue" => task.due.is_some_and(|d| d < today),
+        "today" => within(0),
+        "week" => within(6),
+        "month" => within(30),
+        date => {
+            let date = tasks::parse_date(date).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Invalid due window.")
+            })?;
+            task.due.is_some_and(|d| d <= date)
+        }
+    })
+}
+
+fn filter(all: Vec<Task>, filter: &Filter) -> Result<Vec<Task>, io::Error> {
+    let today = Local::now().date_naive();
+    let mut selected = Vec::new();
+    for task in all {
+        let status = match filter.status.as_str() {
+            "open" => !task.done,
+            "done" => task.done,
+            _ => true,
+        };
+        let project = filter.project.as_ref().is_none_or(|p| task.in_project(p));
+        let tag = filter
+            .tag
+            .as_ref()
+            .is_none_or(|t| task.tags.contains(&t.to_lowercase()));
+        let due = match &filter.due {
+            Some(window) => due_matches(&task, window, today)?,
+            None => true,
+        };
+        if status && project && tag && due {
+            selected.push(task);
+        }
+    }
+    selected.sort_by(|a, b| {
+        (a.due.is_none(), a.due, a.priority.is_none(), a.priority).cmp(&(
+            b.due.is_none(),
+            b.due,
+            b.priority.is_none(),
+            b.priority,
+        ))
+    });
+    Ok(selected)
+}
+
+fn label(task: &Task) -> String {
+    let mut label = format!("[{}] ", if task.done { "x" } else { " " });
+    if let Some(priority) = task.priority {
+        label.push_str(&format!("({}) ", priority));
+    }
+    label.push_str(&task.description);
+    if let Some(due) = task.due {
+        label.push_str(&format!(" (due {})", due.format("%Y-%m-%d")));
+    }
+    label
+}
+
+pub fn list(filter_by: Filter, format: String, config: Config) -> Result<(), io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let tasks = filter(tasks::collect(&notebook), &filter_by)?;
+
+    match format.as_str() {
+        "list" => {
+            for task in &tasks {
+                println!(
+                    "{}  {}  {}:{}",
+                    task.id,
+                    label(task),
+                    task.rel_path,
+                    task.line
+                );
+            }
+        }
+        "json" => {
+            let rows: Vec<Row> = tasks.iter().map(row).collect();
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+        }
+        "picker" => {
+            if tasks.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "No tasks found."));
+            }
+            cliclack::intro(console::style(" Grom ").on_cyan().black()).unwrap();
+            let items: Vec<_> = tasks
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (i, label(t), format!("{}:{}", t.rel_path, t.line)))
+                .collect();
+            let selected = cliclack::select("Select a Task".to_string())
+                .items(&items)
+                .interact()
+                .map_err(|_| io::Error::other("Error selecting task"))?;
+            let task = &tasks[selected];
+            utils::open_file_at(&config.core.editor, &task.path.to_string_lossy(), task.line)?;
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unknown output format.",
+            ))
+        }
+    }
+    Ok(())
+}
+
+fn project_file(project: &str, config: &Config) -> Result<String, io::Error> {
+    let path = utils::project_start(&config.core.note_dir, project);
+    if utils::path_exists(&path) {
+        Ok(path)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Project does not exist.",
+        ))
+    }
+}
+
+fn source_line(task: &Task) -> Result<String, io::Error> {
+    fs::read_to_string(&task.path)?
+        .lines()
+        .nth(task.line - 1)
+        .map(String::from)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Task line no longer exists."))
+}
+
+pub fn done(id: String, config: Config) -> Result<(), io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let all = tasks::collect(&notebook);
+    let task = tasks::find(&all, &id)?;
+    if task.done {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Task is already done.",
+        ));
+    }
+    let line = source_line(task)?;
+    let completed = tasks::complete_line(&line, Local::now().date_naive())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Task line no longer exists."))?;
+    tasks::replace_line(&task.path, task.line, Some(&completed))?;
+    println!("{}  {}", task.id, label(task).replacen("[ ]", "[x]", 1));
+    Ok(())
+}
+
+pub fn add(
+    project: String,
+    text: String,
+    due: Option<String>,
+    config: Config,
+) -> Result<(), io::Error> {
+    let path = project_file(&project, &config)?;
+    let mut line = format!("- [ ] {}", text.trim());
+    if let Some(due) = due {
+        let date = tasks::parse_relative_date(&due, Local::now().date_naive())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid due date."))?;
+        line.push_str(&format!(" due:{}", date.format("%Y-%m-%d")));
+    }
+    tasks::insert_task(Path::new(&path), &line)?;
+
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let all = tasks::collect(&notebook);
+    if let Some(task) = all
+        .iter()
+        .rev()
+        .find(|t| t.path == Path::new(&path) && line.ends_with(&t.text))
+    {
+        println!("{}  {}", task.id, label(task));
+    }
+    Ok(())
+}
+
+pub fn move_task(id: String, project: String, config: Config) -> Result<(), io::Error> {
+    let target = project_file(&project, &config)?;
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let all = tasks::collect(&notebook);
+    let task = tasks::find(&all, &id)?;
+    if task.path == Path::new(&target) {
+        return Ok(());
+    }
+    // The task leaves its note first, which is put back if it cannot be added to the project.
+    let original = fs::read_to_string(&task.path)?;
+    let lines = tasks::take_task(&task.path, task.line)?;
+    if let Err(e) = tasks::insert_tasks(Path::new(&target), &lines) {
+        fs::write(&task.path, original)?;
+        return Err(e);
+    }
+    println!("{}  {} -> {}", task.id, label(task), project);
+    Ok(())
+}
+
+fn write_output(output: &str, out: Option<String>) -> Result<(), io::Error> {
+    match out {
+        Some(path) => {
+            utils::ensure_all_dirs(&path)?;
+            fs::write(path, output)
+        }
+        None => {
+            print!("{}", output);
+            Ok(())
+        }
+    }
+}
+
+fn inbox(config: &Config) -> Result<String, io::Error> {
+    let inbox = format!("{}/quick-notes/todo.md", config.core.note_dir);
+    if !utils::path_exists(&inbox) {
+        utils::ensure_all_dirs(&inbox)?;
+        utils::save_file(&inbox, &meta::initial("todo", &config.core.front_matter)?)?;
+    }
+    Ok(inbox)
+}
+
+fn export_taskwarrior(config: &Config) -> Result<String, io::Error> {
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let today = Local::now().date_naive();
+    let exported: Vec<taskwarrior::TwTask> = tasks::collect(&notebook)
+        .iter()
+        .map(|task| {
+            let entry = notebook
+                .find(&task.rel_path)
+                .and_then(|i| notebook.notes[i].created())
+                .map(|c| c.date())
+                .unwrap_or(today);
+            taskwarrior::from_task(task, entry)
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&exported)? + "\n")
+}
+
+pub fn export(format: String, out: Option<String>, config: Config) -> Result<(), io::Error> {
+    let output = match format.as_str() {
+        "todotxt" => {
+            let notebook = Notebook::load(&config.core.note_dir)?;
+            tasks::collect(&notebook)
+                .iter()
+                .map(|t| todotxt::to_line(t) + "\n")
+                .collect::<String>()
+        }
+        "taskwarrior" => export_taskwarrior(&config)?,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unknown export format.",
+            ))
+        }
+    };
+    write_output(&output, out)
+}
+
+// Changes collected during an import, written once per file at the end so the line numbers
+// of the loaded tasks stay valid.
+#[derive(Default)]
+struct Changes {
+    replaced: BTreeMap<PathBuf, BTreeMap<usize, String>>,
+    inserted: BTreeMap<PathBuf, Vec<String>>,
+}
+
+impl Changes {
+    fn replace(&mut self, task: &Task, markdown: String) -> Result<(), io::Error> {
+        let source = source_line(task)?;
+        let indent = &source[..source.len() - source.trim_start().len()];
+        self.replaced
+            .entry(task.path.clone())
+            .or_default()
+            .insert(task.line, format!("{}{}", indent, markdown));
+        Ok(())
+    }
+
+    fn insert(&mut self, path: &str, line: String) {
+        self.inserted
+            .entry(PathBuf::from(path))
+            .or_default()
+            .push(line);
+    }
+
+    fn write(self) -> Result<(), io::Error> {
+        for (path, lines) in self.replaced {
+            tasks::replace_lines(&path, &lines.into_iter().collect::<Vec<_>>())?;
+        }
+        for (path, lines) in self.inserted {
+            tasks::insert_tasks(&path, &lines)?;
+        }
+        Ok(())
+    }
+}
+
+fn import_todotxt(
+    content: &str,
+    project: Option<String>,
+    config: &Config,
+) -> Result<(), io::Error> {
+    let (mut added, mut updated) = (0, 0);
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let all = tasks::collect(&notebook);
+    let mut changes = Changes::default();
+    let mut seen = HashSet::new();
+    for line in content.lines() {
+        let Some(item) = todotxt::parse_line(line) else {
+            continue;
+        };
+        let key = todotxt::key(&item.text);
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+        let parsed = Task::new(Default::default(), String::new(), 0, false, &item.text);
+
+        if let Some(existing) = all.iter().find(|t| todotxt::key(&t.text) == key) {
+            let same = existing.done == item.done
+                && existing.priority == item.priority
+                && existing.due == parsed.due;
+            if !same {
+                changes.replace(
+                    existing,
+                    todotxt::to_markdown(&item, existing.project.as_deref()),
+                )?;
+                updated += 1;
+            }
+            continue;
+        }
+
+        let target = parsed
+            .projects
+            .iter()
+            .find_map(|p| project_file(p, config).ok().map(|f| (Some(p.clone()), f)))
+            .or_else(|| {
+                project
+                    .as_ref()
+                    .and_then(|p| project_file(p, config).ok().map(|f| (Some(p.clone()), f)))
+            });
+        let (target_project, path) = match target {
+            Some(target) => target,
+            None => (None, inbox(config)?),
+        };
+        changes.insert(
+            &path,
+            todotxt::to_markdown(&item, target_project.as_deref()),
+        );
+        added += 1;
+    }
+    changes.write()?;
+    println!("{} added, {} updated", added, updated);
+    Ok(())
+}
+
+fn import_taskwarrior(
+    content: &str,
+    project: Option<String>,
+    config: &Config,
+) -> Result<(), io::Error> {
+    let (mut added, mut updated) = (0, 0);
+    let today = Local::now().date_naive();
+    let notebook = Notebook::load(&config.core.note_dir)?;
+    let all = tasks::collect(&notebook);
+    let mut changes = Changes::default();
+    let mut seen = HashSet::new();
+    for incoming in taskwarrior::parse(content)? {
+        if incoming.status == "deleted"
+            || incoming.status == "recurring"
+            || !seen.insert(incoming.uuid.clone())
+        {
+            continue;
+        }
+
+        let existing = all
+            .iter()
+            .find(|t| taskwarrior::task_uuid(t) == incoming.uuid);
+        if let Some(existing) = existing {
+            let current = taskwarrior::from_task(existing, today);
+            let mut current_tags = current.tags.clone();
+            let mut incoming_tags = incoming.tags.clone();
+            current_tags.sort();
+            incoming_tags.sort();
+            // to_markdown keeps a project other than the filed one as a `+project` word.
+            let description = match &incoming.project {
+                Some(p) => current
+                    .description
+                    .split_whitespace()
+                    .filter(|w| w.strip_prefix('+') != Some(p.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                None => current.description.clone(),
+            };
+            let same = current.status == incoming.status
+                && description == incoming.description
+                && current.priority == incoming.priority
+                && current.due_date() == incoming.due_date()
+                && current_tags == incoming_tags;
+            if !same {
+                let filed = notebook
+                    .find(&existing.rel_path)
+                    .and_then(|i| notebook.notes[i].project.clone());
+                changes.replace(
+                    existing,
+                    taskwarrior::update_line(existing, &incoming, filed.as_deref()),
+                )?;
+                updated += 1;
+            }
+            continue;
+        }
+
+        let candidates: Vec<String> = incoming
+            .project
+            .iter()
+            .flat_map(|p| [p.clone(), p.split('.').next().unwrap_or(p).to_string()])
+            .chain(project.clone())
+            .collect();
+        let (filed, path) = match candidates
+            .iter()
+            .find_map(|p| project_file(p, config).ok().map(|f| (p.clone(), f)))
+        {
+            Some((p, f)) => (Some(p), f),
+            None => (None, inbox(config)?),
+        };
+        changes.insert(&path, taskwarrior::to_markdown(&incoming, filed.as_deref()));
+        added += 1;
+    }
+    changes.write()?;
+    println!("{} added, {} updated", added, updated);
+    Ok(())
+}
+
+pub fn import(
+    file: String,
+    format: String,
+    project: Option<String>,
+    config: Config,
+) -> Result<(), io::Error> {
+    if let Some(project) = &project {
+        project_file(project, &config)?;
+    }
+    let content = fs::read_to_string(&file)?;
+    match format.as_str() {
+        "todotxt" => import_todotxt(&content, project, &config),
+        "taskwarrior" => import_taskwarrior(&content, project, &config),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Unknown import format.",
+        )),
+    }
+}
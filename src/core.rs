@@ -1,3 +1,20 @@
+pub mod catalog;
 pub mod config;
+pub mod enex;
+pub mod enml;
+pub mod epub;
 pub mod git;
+pub mod html;
+pub mod ics;
+pub mod joplin;
+pub mod markdown;
+pub mod meta;
+pub mod notemerge;
+pub mod notes;
+pub mod query;
+pub mod tasks;
+pub mod taskwarrior;
+#[cfg(test)]
+pub mod testing;
+pub mod todotxt;
 pub mod utils;
@@ -0,0 +1,264 @@
+use crate::core::notes::{Note, Notebook};
+use crate::core::tasks::Task;
+use rusqlite::{params, Connection, OpenFlags};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::{fs, io, path::PathBuf, time::UNIX_EPOCH};
+
+// Bumped whenever the schema changes; an older catalog is rebuilt from scratch.
+const SCHEMA_VERSION: i64 = 2;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS notes (
+    path TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    project TEXT,
+    title TEXT NOT NULL,
+    date TEXT,
+    created TEXT,
+    modified TEXT,
+    status TEXT,
+    mtime INTEGER NOT NULL,
+    body TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS headings (
+    path TEXT NOT NULL,
+    level INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    line INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS tags (
+    path TEXT NOT NULL,
+    tag TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS tasks (
+    path TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    done INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    due TEXT,
+    priority TEXT,
+    project TEXT
+);
+CREATE TABLE IF NOT EXISTS links (
+    source TEXT NOT NULL,
+    target TEXT NOT NULL,
+    resolved TEXT
+);
+CREATE INDEX IF NOT EXISTS headings_path ON headings(path);
+CREATE INDEX IF NOT EXISTS tags_path ON tags(path);
+CREATE INDEX IF NOT EXISTS tasks_path ON tasks(path);
+CREATE INDEX IF NOT EXISTS links_source ON links(source);
+";
+
+fn sql_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(format!("Catalog error: {e}"))
+}
+
+// Every notebook gets its own catalog, named after a hash of its directory.
+pub fn catalog_path(note_dir: &str) -> Result<PathBuf, io::Error> {
+    let dir = dirs::cache_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No cache directory."))?
+        .join("grom");
+    fs::create_dir_all(&dir)?;
+    let note_dir = fs::canonicalize(note_dir).unwrap_or_else(|_| PathBuf::from(note_dir));
+    let hash = format!(
+        "{:x}",
+        Sha256::digest(note_dir.to_string_lossy().as_bytes())
+    );
+    Ok(dir.join(format!("catalog-{}.db", &hash[..16])))
+}
+
+fn migrate(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version != SCHEMA_VERSION {
+        for table in ["notes", "headings", "tags", "tasks", "links"] {
+            conn.execute(&format!("DROP TABLE IF EXISTS {}", table), [])?;
+        }
+    }
+    conn.execute_batch(SCHEMA)?;
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+}
+
+fn mtime(note: &Note) -> i64 {
+    fs::metadata(&note.path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or(0)
+}
+
+fn format_datetime(value: Option<chrono::NaiveDateTime>) -> Option<String> {
+    value.map(|d| d.format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+fn insert_note(tx: &rusqlite::Transaction, note: &Note, mtime: i64) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "INSERT INTO notes (path, kind, project, title, date, created, modified, status, mtime, body)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            note.rel_path,
+            note.kind.as_str(),
+            note.project,
+            note.title(),
+            note.date.map(|d| d.format("%Y-%m-%d").to_string()),
+            format_datetime(note.created()),
+            format_datetime(note.modified()),
+            note.meta.status,
+            mtime,
+            note.body(),
+        ],
+    )?;
+    for heading in note.headings() {
+        tx.execute(
+            "INSERT INTO headings (path, level, text, line) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                note.rel_path,
+                heading.level as i64,
+                heading.text,
+                heading.line as i64
+            ],
+        )?;
+    }
+    for tag in note.tags() {
+        tx.execute(
+            "INSERT INTO tags (path, tag) VALUES (?1, ?2)",
+            params![note.rel_path, tag],
+        )?;
+    }
+    for item in note.checkboxes() {
+        let task = Task::new(
+            note.path.clone(),
+            note.rel_path.clone(),
+            item.line,
+            item.done,
+            &item.text,
+        );
+        let project = note
+            .project
+            .clone()
+            .or_else(|| task.projects.first().cloned());
+        tx.execute(
+            "INSERT INTO tasks (path, line, done, text, due, priority, project)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                note.rel_path,
+                item.line as i64,
+                item.done,
+                item.text,
+                task.due.map(|d| d.format("%Y-%m-%d").to_string()),
+                task.priority.map(String::from),
+                project,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn delete_note(tx: &rusqlite::Transaction, path: &str) -> Result<(), rusqlite::Error> {
+    for table in ["notes", "headings", "tags", "tasks"] {
+        tx.execute(&format!("DELETE FROM {} WHERE path = ?1", table), [path])?;
+    }
+    Ok(())
+}
+
+pub fn sync(note_dir: &str) -> Result<PathBuf, io::Error> {
+    let path = catalog_path(note_dir)?;
+    let notebook = Notebook::load(note_dir)?;
+    let mut conn = Connection::open(&path).map_err(sql_error)?;
+    migrate(&conn).map_err(sql_error)?;
+
+    let mut known: HashMap<String, i64> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT path, mtime FROM notes")
+            .map_err(sql_error)?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(sql_error)?;
+        for row in rows {
+            let (path, mtime) = row.map_err(sql_error)?;
+            known.insert(path, mtime);
+        }
+    }
+
+    let tx = conn.transaction().map_err(sql_error)?;
+    for note in &notebook.notes {
+        let mtime = mtime(note);
+        match known.remove(&note.rel_path) {
+            Some(stored) if stored == mtime => {}
+            Some(_) => {
+                delete_note(&tx, &note.rel_path).map_err(sql_error)?;
+                insert_note(&tx, note, mtime).map_err(sql_error)?;
+            }
+            None => insert_note(&tx, note, mtime).map_err(sql_error)?,
+        }
+    }
+    for removed in known.keys() {
+        delete_note(&tx, removed).map_err(sql_error)?;
+    }
+
+    // Link resolution depends on the whole notebook, so links are rebuilt on every sync.
+    tx.execute("DELETE FROM links", []).map_err(sql_error)?;
+    for (i, note) in notebook.notes.iter().enumerate() {
+        for link in note.links() {
+            let resolved = notebook
+                .resolve(i, &link)
+                .map(|t| notebook.notes[t].rel_path.clone());
+            tx.execute(
+                "INSERT INTO links (source, target, resolved) VALUES (?1, ?2, ?3)",
+                params![note.rel_path, link.target, resolved],
+            )
+            .map_err(sql_error)?;
+        }
+    }
+    tx.commit().map_err(sql_error)?;
+    Ok(path)
+}
+
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+}
+
+pub fn query(note_dir: &str, sql: &str) -> Result<QueryResult, io::Error> {
+    let path = sync(note_dir)?;
+    let conn =
+        Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(sql_error)?;
+    let mut stmt = conn.prepare(sql).map_err(|e| match e {
+        rusqlite::Error::MultipleStatement => io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Only a single statement is allowed.",
+        ),
+        e => sql_error(e),
+    })?;
+    if !stmt.readonly() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Only read-only statements are allowed.",
+        ));
+    }
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let mut rows = Vec::new();
+    let mut result = stmt.query([]).map_err(sql_error)?;
+    while let Some(row) = result.next().map_err(sql_error)? {
+        let mut values = Vec::new();
+        for i in 0..columns.len() {
+            let value = match row.get_ref(i).map_err(sql_error)? {
+                rusqlite::types::ValueRef::Null => serde_json::Value::Null,
+                rusqlite::types::ValueRef::Integer(v) => serde_json::Value::from(v),
+                rusqlite::types::ValueRef::Real(v) => serde_json::Value::from(v),
+                rusqlite::types::ValueRef::Text(v) => {
+                    serde_json::Value::from(String::from_utf8_lossy(v).to_string())
+                }
+                rusqlite::types::ValueRef::Blob(v) => {
+                    serde_json::Value::from(format!("<{} bytes>", v.len()))
+                }
+            };
+            values.push(value);
+        }
+        rows.push(values);
+    }
+    Ok(QueryResult { columns, rows })
+}
@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::io::ErrorKind;
 use std::{fs, io};
 use toml;
@@ -11,22 +12,109 @@ fn default_editor() -> String {
     "nvim".to_string()
 }
 
+fn default_front_matter() -> String {
+    "none".to_string()
+}
+
 #[derive(Deserialize)]
 pub struct Core {
     #[serde(default = "default_note_dir")]
     pub note_dir: String,
     #[serde(default = "default_editor")]
     pub editor: String,
+    #[serde(default = "default_front_matter")]
+    pub front_matter: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct Meetings {
+    pub template: Option<String>,
+    #[serde(default)]
+    pub keywords: BTreeMap<String, String>,
+}
+
+fn default_feed_title() -> String {
+    "Diary".to_string()
+}
+
+fn default_feed_period() -> String {
+    "weekly".to_string()
+}
+
+fn default_feed_limit() -> usize {
+    10
+}
+
+#[derive(Deserialize)]
+pub struct Feed {
+    #[serde(default = "default_feed_title")]
+    pub title: String,
+    pub url: Option<String>,
+    pub author: Option<String>,
+    #[serde(default = "default_feed_period")]
+    pub period: String,
+    #[serde(default = "default_feed_limit")]
+    pub limit: usize,
+}
+
+impl Default for Feed {
+    fn default() -> Self {
+        Feed {
+            title: default_feed_title(),
+            url: None,
+            author: None,
+            period: default_feed_period(),
+            limit: default_feed_limit(),
+        }
+    }
+}
+
+fn default_sync_remote() -> String {
+    "origin".to_string()
+}
+
+#[derive(Deserialize)]
+pub struct Sync {
+    #[serde(default = "default_sync_remote")]
+    pub remote: String,
+    pub branch: Option<String>,
+}
+
+impl Default for Sync {
+    fn default() -> Self {
+        Sync {
+            remote: default_sync_remote(),
+            branch: None,
+        }
+    }
 }
 
 #[derive(Deserialize)]
 pub struct Config {
     pub core: Core,
+    #[serde(default)]
+    pub meetings: Meetings,
+    #[serde(default)]
+    pub feed: Feed,
+    #[serde(default)]
+    pub sync: Sync,
 }
 
-pub fn load_config() -> Result<Config, io::Error> {
+pub fn config_dir() -> String {
     let home_path = String::from(dirs::home_dir().unwrap().to_str().unwrap());
-    let filename = home_path + "/.config/grom/grom.toml";
+    home_path + "/.config/grom"
+}
+
+pub fn expand_home(path: &str) -> String {
+    if path.starts_with("~") {
+        path.replacen("~", dirs::home_dir().unwrap().to_str().unwrap(), 1)
+    } else {
+        path.to_string()
+    }
+}
+
+pub fn load_config() -> Result<Config, io::Error> {
+    let filename = config_dir() + "/grom.toml";
 
     let contents = fs::read_to_string(filename.clone())?;
 
@@ -0,0 +1,106 @@
+use crate::core::enml;
+use base64::Engine;
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use md5::{Digest, Md5};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io;
+
+#[derive(Default)]
+pub struct Resource {
+    pub hash: String,
+    pub mime: String,
+    pub file_name: Option<String>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct EnexNote {
+    pub title: String,
+    pub content: String,
+    pub created: Option<String>,
+    pub updated: Option<String>,
+    pub tags: Vec<String>,
+    pub source_url: Option<String>,
+    pub resources: Vec<Resource>,
+}
+
+// ENEX timestamps are UTC, like 20240305T091200Z.
+fn timestamp(value: &str) -> Option<String> {
+    let utc = NaiveDateTime::parse_from_str(value.trim(), "%Y%m%dT%H%M%SZ").ok()?;
+    Some(
+        Utc.from_utc_datetime(&utc)
+            .with_timezone(&Local)
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string(),
+    )
+}
+
+pub fn parse(xml: &str) -> Result<Vec<EnexNote>, io::Error> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(false);
+    let mut notes = Vec::new();
+    let mut note = EnexNote::default();
+    let mut resource = Resource::default();
+    let mut path: Vec<String> = Vec::new();
+    let mut text = String::new();
+    loop {
+        let event = reader.read_event().map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid ENEX file: {}", e),
+            )
+        })?;
+        match event {
+            Event::Start(element) => {
+                let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+                match name.as_str() {
+                    "note" => note = EnexNote::default(),
+                    "resource" => resource = Resource::default(),
+                    _ => {}
+                }
+                path.push(name);
+                text.clear();
+            }
+            Event::Text(content) => {
+                text.push_str(&enml::decode(&String::from_utf8_lossy(&content)))
+            }
+            Event::CData(content) => text.push_str(&String::from_utf8_lossy(&content)),
+            Event::End(_) => {
+                let current: Vec<&str> = path.iter().map(String::as_str).collect();
+                match current.as_slice() {
+                    [.., "note", "title"] => note.title = text.trim().to_string(),
+                    [.., "note", "content"] => note.content = text.clone(),
+                    [.., "note", "created"] => note.created = timestamp(&text),
+                    [.., "note", "updated"] => note.updated = timestamp(&text),
+                    [.., "note", "tag"] => note.tags.push(text.trim().to_string()),
+                    [.., "note-attributes", "source-url"] => {
+                        note.source_url = Some(text.trim().to_string())
+                    }
+                    [.., "resource", "data"] => {
+                        let encoded: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+                        resource.data = base64::engine::general_purpose::STANDARD
+                            .decode(encoded)
+                            .unwrap_or_default();
+                    }
+                    [.., "resource", "mime"] => resource.mime = text.trim().to_string(),
+                    [.., "resource-attributes", "file-name"] => {
+                        resource.file_name = Some(text.trim().to_string())
+                    }
+                    [.., "note", "resource"] => {
+                        // en-media elements refer to resources by the MD5 hash of their data.
+                        resource.hash = format!("{:x}", Md5::digest(&resource.data));
+                        note.resources.push(std::mem::take(&mut resource));
+                    }
+                    [.., "note"] => notes.push(std::mem::take(&mut note)),
+                    _ => {}
+                }
+                path.pop();
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(notes)
+}
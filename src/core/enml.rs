@@ -0,0 +1,517 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+// Elements whose content cannot be represented in markdown.
+const SKIPPED: [&str; 16] = [
+    "script", "style", "head", "object", "embed", "iframe", "video", "audio", "svg", "canvas",
+    "form", "input", "button", "select", "textarea", "applet",
+];
+
+const VOID: [&str; 10] = [
+    "br", "hr", "img", "input", "meta", "link", "en-media", "en-todo", "col", "wbr",
+];
+
+/// Markdown converted from ENML or HTML, with a note for every construct that got lost.
+pub struct Conversion {
+    pub markdown: String,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Role {
+    Paragraph,
+    Line,
+    Heading,
+    List,
+    Item,
+    Quote,
+    Pre,
+    Code,
+    Strong,
+    Emphasis,
+    Strike,
+    Link,
+    Table,
+    Row,
+    Cell,
+    Inline,
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "bull" => '•',
+        "middot" => '·',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "euro" => '€',
+        _ => return None,
+    })
+}
+
+// Resolves character references, including the HTML ones XML does not know about.
+pub fn decode(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let resolved = after
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((entity(&after[..end])?, end)));
+        match resolved {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.as_ref().eq_ignore_ascii_case(name.as_bytes()))
+        .map(|a| decode(&String::from_utf8_lossy(&a.value)))
+}
+
+struct Converter<'a> {
+    buffers: Vec<String>,
+    stack: Vec<(String, Role)>,
+    // Ordered lists keep their counter, every list the width of its current marker.
+    lists: Vec<(Option<usize>, usize)>,
+    tables: Vec<Vec<Vec<String>>>,
+    links: Vec<Option<String>>,
+    items: usize,
+    pre: usize,
+    skip: usize,
+    warnings: Vec<String>,
+    media: &'a mut dyn FnMut(&str, &str) -> Option<String>,
+}
+
+impl Converter<'_> {
+    fn out(&mut self) -> &mut String {
+        if self.buffers.is_empty() {
+            self.buffers.push(String::new());
+        }
+        self.buffers.last_mut().unwrap()
+    }
+
+    fn warn(&mut self, warning: String) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    fn newline(&mut self) {
+        let out = self.out();
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+
+    fn blank(&mut self) {
+        self.newline();
+        let out = self.out();
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.skip > 0 {
+            return;
+        }
+        if self.pre > 0 {
+            self.out().push_str(text);
+            return;
+        }
+        let out = self.buffers.last_mut().unwrap();
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !out.is_empty() && !out.ends_with([' ', '\n']) {
+                    out.push(' ');
+                }
+                continue;
+            }
+            if matches!(c, '\\' | '*' | '`' | '[' | ']') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+
+    fn start(&mut self, element: &BytesStart, empty: bool) {
+        let name = String::from_utf8_lossy(element.name().as_ref()).to_lowercase();
+        let void = VOID.contains(&name.as_str());
+        if self.skip > 0 {
+            if !empty && !void {
+                self.skip += 1;
+            }
+            return;
+        }
+        if SKIPPED.contains(&name.as_str()) || name == "en-crypt" {
+            let warning = match name.as_str() {
+                "en-crypt" => "encrypted content skipped".to_string(),
+                "head" | "style" | "script" => String::new(),
+                _ => format!("unsupported <{}> element skipped", name),
+            };
+            if !warning.is_empty() {
+                self.warn(warning);
+            }
+            if !empty && !void {
+                self.skip = 1;
+            }
+            return;
+        }
+        let role = match name.as_str() {
+            "br" => {
+                self.out().push('\n');
+                return;
+            }
+            "hr" => {
+                self.blank();
+                self.out().push_str("---");
+                self.blank();
+                return;
+            }
+            "img" => {
+                let source = attribute(element, "src").unwrap_or_default();
+                let alt = attribute(element, "alt").unwrap_or_default();
+                if source.starts_with("data:") {
+                    self.warn("embedded image data skipped".to_string());
+                } else if !source.is_empty() {
+                    self.out().push_str(&format!("![{}]({})", alt, source));
+                }
+                return;
+            }
+            "en-media" => {
+                let hash = attribute(element, "hash").unwrap_or_default();
+                let mime = attribute(element, "type").unwrap_or_default();
+                match (self.media)(&hash, &mime) {
+                    Some(markdown) => self.out().push_str(&markdown),
+                    None => self.warn(format!("attachment {} not found", hash)),
+                }
+                return;
+            }
+            "en-todo" => {
+                let checked = attribute(element, "checked").is_some_and(|c| c == "true");
+                let checkbox = if checked { "[x] " } else { "[ ] " };
+                if self.items == 0 {
+                    self.newline();
+                    self.out().push_str("- ");
+                }
+                self.out().push_str(checkbox);
+                return;
+            }
+            _ if void => return,
+            "div" if attribute(element, "style").is_some_and(|s| s.contains("-en-codeblock")) => {
+                self.buffers.push(String::new());
+                self.pre += 1;
+                Role::Pre
+            }
+            "div" => {
+                if self.items == 0 {
+                    self.newline();
+                }
+                Role::Line
+            }
+            "p" | "section" | "article" | "header" | "footer" | "main" | "aside" | "figure"
+            | "address" | "center" | "dl" | "dt" | "dd" => {
+                if self.items == 0 {
+                    self.blank();
+                }
+                Role::Paragraph
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.blank();
+                let level = name[1..].parse().unwrap_or(1);
+                self.out().push_str(&format!("{} ", "#".repeat(level)));
+                Role::Heading
+            }
+            "ul" | "ol" => {
+                if self.items > 0 {
+                    self.newline();
+                } else {
+                    self.blank();
+                }
+                let counter = (name == "ol").then(|| {
+                    attribute(element, "start")
+                        .and_then(|s| s.parse().ok())
+                        .unwrap_or(1)
+                });
+                self.lists.push((counter, 2));
+                Role::List
+            }
+            "li" => {
+                self.newline();
+                if self.lists.is_empty() {
+                    self.lists.push((None, 2));
+                }
+                let depth = self.lists.len() - 1;
+                let indent: usize = self.lists[..depth].iter().map(|l| l.1).sum();
+                let list = self.lists.last_mut().unwrap();
+                let marker = match &mut list.0 {
+                    Some(counter) => {
+                        *counter += 1;
+                        format!("{}. ", *counter - 1)
+                    }
+                    None => "- ".to_string(),
+                };
+                list.1 = marker.len();
+                self.items += 1;
+                self.out()
+                    .push_str(&format!("{}{}", " ".repeat(indent), marker));
+                Role::Item
+            }
+            "blockquote" => {
+                self.buffers.push(String::new());
+                Role::Quote
+            }
+            "pre" => {
+                self.buffers.push(String::new());
+                self.pre += 1;
+                Role::Pre
+            }
+            "code" | "tt" | "kbd" | "samp" if self.pre == 0 => {
+                self.out().push('`');
+                Role::Code
+            }
+            "b" | "strong" => {
+                self.out().push_str("**");
+                Role::Strong
+            }
+            "i" | "em" => {
+                self.out().push('*');
+                Role::Emphasis
+            }
+            "s" | "strike" | "del" => {
+                self.out().push_str("~~");
+                Role::Strike
+            }
+            "a" => {
+                self.buffers.push(String::new());
+                self.links.push(attribute(element, "href"));
+                Role::Link
+            }
+            "table" => {
+                self.blank();
+                self.tables.push(Vec::new());
+                Role::Table
+            }
+            "tr" => {
+                if let Some(table) = self.tables.last_mut() {
+                    table.push(Vec::new());
+                }
+                Role::Row
+            }
+            "td" | "th" => {
+                self.buffers.push(String::new());
+                Role::Cell
+            }
+            _ => Role::Inline,
+        };
+        if empty {
+            self.close(role);
+        } else {
+            self.stack.push((name, role));
+        }
+    }
+
+    fn end(&mut self, name: &[u8]) {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return;
+        }
+        let name = String::from_utf8_lossy(name).to_lowercase();
+        // Elements left open in sloppy HTML are closed together with their parent.
+        if let Some(position) = self.stack.iter().rposition(|(n, _)| *n == name) {
+            while self.stack.len() > position {
+                let (_, role) = self.stack.pop().unwrap();
+                self.close(role);
+            }
+        }
+    }
+
+    fn pop(&mut self) -> String {
+        if self.buffers.len() > 1 {
+            self.buffers.pop().unwrap()
+        } else {
+            String::new()
+        }
+    }
+
+    fn close(&mut self, role: Role) {
+        match role {
+            Role::Paragraph | Role::Line if self.items > 0 => {
+                let out = self.out();
+                if !out.ends_with([' ', '\n']) {
+                    out.push(' ');
+                }
+            }
+            Role::Paragraph | Role::Heading => self.blank(),
+            Role::Line => self.newline(),
+            Role::List => {
+                self.lists.pop();
+                if self.items > 0 {
+                    self.newline();
+                } else {
+                    self.blank();
+                }
+            }
+            Role::Item => {
+                self.items = self.items.saturating_sub(1);
+                self.newline();
+            }
+            Role::Quote => {
+                let quote = self.pop();
+                self.blank();
+                let quoted: Vec<String> = quote
+                    .trim()
+                    .lines()
+                    .map(|l| format!("> {}", l).trim_end().to_string())
+                    .collect();
+                self.out().push_str(&quoted.join("\n"));
+                self.blank();
+            }
+            Role::Pre => {
+                self.pre = self.pre.saturating_sub(1);
+                let code = self.pop();
+                self.blank();
+                self.out()
+                    .push_str(&format!("```\n{}\n```", code.trim_matches('\n')));
+                self.blank();
+            }
+            Role::Code => self.out().push('`'),
+            Role::Strong => self.out().push_str("**"),
+            Role::Emphasis => self.out().push('*'),
+            Role::Strike => self.out().push_str("~~"),
+            Role::Link => {
+                let text = self.pop().replace('\n', " ").trim().to_string();
+                let href = self.links.pop().flatten().unwrap_or_default();
+                if href.starts_with("evernote:") {
+                    self.warn("links to other Evernote notes kept as they are".to_string());
+                }
+                let link = match (text.is_empty(), href.is_empty()) {
+                    (_, true) => text,
+                    (true, false) => format!("<{}>", href),
+                    (false, false) => format!("[{}]({})", text, href),
+                };
+                self.out().push_str(&link);
+            }
+            Role::Table => {
+                let rows = self.tables.pop().unwrap_or_default();
+                let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+                if width > 0 {
+                    let mut table = Vec::new();
+                    for (i, row) in rows.iter().filter(|r| !r.is_empty()).enumerate() {
+                        let mut cells = row.clone();
+                        cells.resize(width, String::new());
+                        table.push(format!("| {} |", cells.join(" | ")));
+                        if i == 0 {
+                            table.push(format!("|{}", " --- |".repeat(width)));
+                        }
+                    }
+                    self.out().push_str(&table.join("\n"));
+                }
+                self.blank();
+            }
+            Role::Cell => {
+                let cell = self.pop();
+                let cell = cell.split_whitespace().collect::<Vec<_>>().join(" ");
+                let cell = cell.replace('|', "\\|");
+                if let Some(table) = self.tables.last_mut() {
+                    if table.is_empty() {
+                        table.push(Vec::new());
+                    }
+                    table.last_mut().unwrap().push(cell);
+                }
+            }
+            Role::Row | Role::Inline => {}
+        }
+    }
+}
+
+/// Converts ENML, Evernote's XHTML dialect, or plain HTML into markdown. `<en-media>` elements
+/// are handed to `media` with their hash and mime type, which returns the markdown to insert.
+pub fn to_markdown(
+    source: &str,
+    media: &mut dyn FnMut(&str, &str) -> Option<String>,
+) -> Conversion {
+    let mut reader = Reader::from_str(source);
+    let config = reader.config_mut();
+    config.trim_text(false);
+    config.check_end_names = false;
+    config.allow_unmatched_ends = true;
+
+    let mut converter = Converter {
+        buffers: vec![String::new()],
+        stack: Vec::new(),
+        lists: Vec::new(),
+        tables: Vec::new(),
+        links: Vec::new(),
+        items: 0,
+        pre: 0,
+        skip: 0,
+        warnings: Vec::new(),
+        media,
+    };
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) => converter.start(&element, false),
+            Ok(Event::Empty(element)) => converter.start(&element, true),
+            Ok(Event::End(element)) => converter.end(element.name().as_ref()),
+            Ok(Event::Text(text)) => converter.text(&decode(&String::from_utf8_lossy(&text))),
+            Ok(Event::CData(text)) => converter.text(&String::from_utf8_lossy(&text)),
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(_) => {
+                converter.warn("malformed markup, the rest of the note was skipped".to_string());
+                break;
+            }
+        }
+    }
+    while let Some((_, role)) = converter.stack.pop() {
+        converter.close(role);
+    }
+
+    let markdown = converter.buffers.concat();
+    let mut lines: Vec<&str> = Vec::new();
+    for line in markdown.lines().map(str::trim_end) {
+        if !(line.is_empty() && lines.last().is_some_and(|l| l.is_empty())) {
+            lines.push(line);
+        }
+    }
+    Conversion {
+        markdown: format!("{}\n", lines.join("\n").trim()),
+        warnings: converter.warnings,
+    }
+}
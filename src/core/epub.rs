@@ -0,0 +1,192 @@
+use crate::core::html::escape;
+use chrono::Utc;
+use std::fs::File;
+use std::io::{self, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+pub struct Chapter {
+    pub title: String,
+    pub file: String,
+    pub body: String,
+}
+
+pub struct Resource {
+    pub file: String,
+    pub data: Vec<u8>,
+}
+
+pub struct Book {
+    pub title: String,
+    pub chapters: Vec<Chapter>,
+    pub resources: Vec<Resource>,
+}
+
+const STYLE: &str = "body { font-family: serif; line-height: 1.5; }\npre, code { font-family: monospace; }\nimg { max-width: 100%; }\n";
+
+fn media_type(file: &str) -> &'static str {
+    let extension = file.rsplit('.').next().unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+fn xhtml(title: &str, stylesheet: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+<title>{}</title>
+<link rel="stylesheet" type="text/css" href="{}"/>
+</head>
+<body>
+{}
+</body>
+</html>
+"#,
+        escape(title),
+        stylesheet,
+        body
+    )
+}
+
+impl Book {
+    fn opf(&self, id: &str) -> String {
+        let mut manifest = String::from(
+            "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n    <item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>\n    <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n",
+        );
+        let mut spine = String::new();
+        for (i, chapter) in self.chapters.iter().enumerate() {
+            manifest.push_str(&format!(
+                "    <item id=\"chapter{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+                i,
+                escape(&chapter.file)
+            ));
+            spine.push_str(&format!("    <itemref idref=\"chapter{}\"/>\n", i));
+        }
+        for (i, resource) in self.resources.iter().enumerate() {
+            manifest.push_str(&format!(
+                "    <item id=\"resource{}\" href=\"{}\" media-type=\"{}\"/>\n",
+                i,
+                escape(&resource.file),
+                media_type(&resource.file)
+            ));
+        }
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="id">{}</dc:identifier>
+    <dc:title>{}</dc:title>
+    <dc:language>en</dc:language>
+    <meta property="dcterms:modified">{}</meta>
+  </metadata>
+  <manifest>
+{}  </manifest>
+  <spine toc="ncx">
+{}  </spine>
+</package>
+"#,
+            id,
+            escape(&self.title),
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+            manifest,
+            spine
+        )
+    }
+
+    fn nav(&self) -> String {
+        let mut items = String::new();
+        for chapter in &self.chapters {
+            items.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                escape(&chapter.file),
+                escape(&chapter.title)
+            ));
+        }
+        xhtml(
+            &self.title,
+            "style.css",
+            &format!(
+                "<nav epub:type=\"toc\" id=\"toc\">\n<h1>{}</h1>\n<ol>\n{}</ol>\n</nav>",
+                escape(&self.title),
+                items
+            ),
+        )
+    }
+
+    // The NCX table of contents is only read by EPUB 2 readers.
+    fn ncx(&self, id: &str) -> String {
+        let mut points = String::new();
+        for (i, chapter) in self.chapters.iter().enumerate() {
+            points.push_str(&format!(
+                "    <navPoint id=\"point{}\" playOrder=\"{}\"><navLabel><text>{}</text></navLabel><content src=\"{}\"/></navPoint>\n",
+                i,
+                i + 1,
+                escape(&chapter.title),
+                escape(&chapter.file)
+            ));
+        }
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1">
+  <head><meta name="dtb:uid" content="{}"/></head>
+  <docTitle><text>{}</text></docTitle>
+  <navMap>
+{}  </navMap>
+</ncx>
+"#,
+            id,
+            escape(&self.title),
+            points
+        )
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), io::Error> {
+        let id = format!("urn:uuid:{}", uuid::Uuid::new_v4());
+        let mut zip = ZipWriter::new(File::create(path)?);
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        // The mimetype entry has to come first and uncompressed.
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(b"application/epub+zip")?;
+        zip.start_file("META-INF/container.xml", deflated)?;
+        zip.write_all(
+            br#"<?xml version="1.0" encoding="utf-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#,
+        )?;
+        let files = [
+            ("OEBPS/content.opf".to_string(), self.opf(&id).into_bytes()),
+            ("OEBPS/nav.xhtml".to_string(), self.nav().into_bytes()),
+            ("OEBPS/toc.ncx".to_string(), self.ncx(&id).into_bytes()),
+            ("OEBPS/style.css".to_string(), STYLE.as_bytes().to_vec()),
+        ];
+        for (name, data) in files {
+            zip.start_file(name, deflated)?;
+            zip.write_all(&data)?;
+        }
+        for chapter in &self.chapters {
+            zip.start_file(format!("OEBPS/{}", chapter.file), deflated)?;
+            zip.write_all(xhtml(&chapter.title, "../style.css", &chapter.body).as_bytes())?;
+        }
+        for resource in &self.resources {
+            zip.start_file(format!("OEBPS/{}", resource.file), deflated)?;
+            zip.write_all(&resource.data)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
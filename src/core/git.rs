@@ -1,90 +1,22 @@
-use git2::{self, FetchOptions, IndexAddOption, PushOptions, RemoteCallbacks, Repository};
+use crate::core::notemerge;
+use git2::{
+    self, build::CheckoutBuilder, AnnotatedCommit, FetchOptions, IndexAddOption, PushOptions,
+    RemoteCallbacks, Repository, RepositoryState, StatusOptions,
+};
 
-pub fn init_sync(path: String, remote: String) -> Result<(), std::io::Error> {
-    let repo: Repository = Repository::init(path.clone()).map_err(|e| {
-        std::io::Error::new(std::io::ErrorKind::Other, format!("Git init failed: {e}"))
-    })?;
-    repo.remote_set_url("origin", remote.as_str())
-        .map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Setting remote URL failed: {e}"),
-            )
-        })?;
-    push_initial_changes(path)?;
-    Ok(())
+/// What a pull did to the local branch.
+pub enum PullOutcome {
+    UpToDate,
+    FastForward,
+    Merged,
+    Rebased,
+    // Paths of the files left with conflict markers.
+    Conflicts(Vec<String>),
 }
 
-pub fn push_initial_changes(path: String) -> Result<(), std::io::Error> {
-    let repo: Repository = Repository::open(path).map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Opening repository failed: {e}"),
-        )
-    })?;
-
-    let mut index = repo.index().map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Getting index failed: {e}"),
-        )
-    })?;
-    index
-        .add_all(["*"].iter(), IndexAddOption::DEFAULT, None)
-        .map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Adding files to index failed: {e}"),
-            )
-        })?;
-    index.write().map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Writing index failed: {e}"),
-        )
-    })?;
-
-    let tree_id = index.write_tree().map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Writing tree failed: {e}"),
-        )
-    })?;
-    let tree = repo.find_tree(tree_id).map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Finding tree failed: {e}"),
-        )
-    })?;
-
-    let signature = repo.signature().map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Creating signature failed: {e}"),
-        )
-    })?;
-    repo.commit(
-        Some("refs/heads/main"),
-        &signature,
-        &signature,
-        "Initial",
-        &tree,
-        &[],
-    )
-    .map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Committing changes failed: {e}"),
-        )
-    })?;
-
-    let mut remote = repo.find_remote("origin").map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Finding remote failed: {e}"),
-        )
-    })?;
-    let mut callbacks = git2::RemoteCallbacks::new();
+// Credentials come from the ssh agent, or are asked for once.
+fn remote_callbacks<'a>() -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
 
     let mut auth_attempts = 0;
     let max_auth_attempts = 1;
@@ -125,304 +57,471 @@ pub fn push_initial_changes(path: String) -> Result<(), std::io::Error> {
             }
         }
     });
+    callbacks
+}
+
+// The configured branch, or the one HEAD points to. Committing on one branch and pushing
+// another would leave the notes in a confusing state, so a mismatch is an error.
+fn sync_branch(repo: &Repository, configured: Option<&str>) -> Result<String, std::io::Error> {
+    let current = repo
+        .find_reference("HEAD")
+        .ok()
+        .and_then(|head| head.symbolic_target().map(String::from))
+        .and_then(|target| target.strip_prefix("refs/heads/").map(String::from));
+    match (configured, current) {
+        (Some(configured), Some(current)) if configured != current => Err(std::io::Error::other(
+            format!("Notes are on branch {current}, but sync is configured for {configured}."),
+        )),
+        (Some(configured), _) => Ok(configured.to_string()),
+        (None, Some(current)) => Ok(current),
+        (None, None) => Ok("main".to_string()),
+    }
+}
+
+// Asks the remote for its default branch, which is unknown for an empty repository.
+fn remote_default_branch(remote: &mut git2::Remote) -> Option<String> {
+    let connection = remote
+        .connect_auth(git2::Direction::Fetch, Some(remote_callbacks()), None)
+        .ok()?;
+    let branch = connection.default_branch().ok()?;
+    branch
+        .as_str()
+        .and_then(|b| b.strip_prefix("refs/heads/"))
+        .map(String::from)
+}
+
+pub fn init_sync(
+    path: String,
+    remote_url: String,
+    remote_name: String,
+    branch: Option<String>,
+) -> Result<(), std::io::Error> {
+    let repo: Repository = Repository::init(path.clone())
+        .map_err(|e| std::io::Error::other(format!("Git init failed: {e}")))?;
+    let mut remote = match repo.find_remote(&remote_name) {
+        Ok(_) => {
+            repo.remote_set_url(&remote_name, remote_url.as_str())
+                .map_err(|e| std::io::Error::other(format!("Setting remote URL failed: {e}")))?;
+            repo.find_remote(&remote_name)
+        }
+        Err(_) => repo.remote(&remote_name, remote_url.as_str()),
+    }
+    .map_err(|e| std::io::Error::other(format!("Setting remote URL failed: {e}")))?;
+    let branch = branch
+        .or_else(|| remote_default_branch(&mut remote))
+        .unwrap_or_else(|| "main".to_string());
+    push_initial_changes(path, remote_name, branch)?;
+    Ok(())
+}
+
+pub fn push_initial_changes(
+    path: String,
+    remote_name: String,
+    branch: String,
+) -> Result<(), std::io::Error> {
+    let repo: Repository = Repository::open(path)
+        .map_err(|e| std::io::Error::other(format!("Opening repository failed: {e}")))?;
+
+    let mut index = repo
+        .index()
+        .map_err(|e| std::io::Error::other(format!("Getting index failed: {e}")))?;
+    index
+        .add_all(["*"].iter(), IndexAddOption::DEFAULT, None)
+        .map_err(|e| std::io::Error::other(format!("Adding files to index failed: {e}")))?;
+    index
+        .write()
+        .map_err(|e| std::io::Error::other(format!("Writing index failed: {e}")))?;
+
+    let tree_id = index
+        .write_tree()
+        .map_err(|e| std::io::Error::other(format!("Writing tree failed: {e}")))?;
+    let tree = repo
+        .find_tree(tree_id)
+        .map_err(|e| std::io::Error::other(format!("Finding tree failed: {e}")))?;
+
+    let signature = repo
+        .signature()
+        .map_err(|e| std::io::Error::other(format!("Creating signature failed: {e}")))?;
+    let refname = format!("refs/heads/{}", branch);
+    repo.commit(
+        Some(&refname),
+        &signature,
+        &signature,
+        "Initial",
+        &tree,
+        &[],
+    )
+    .map_err(|e| std::io::Error::other(format!("Committing changes failed: {e}")))?;
+    repo.set_head(&refname)
+        .map_err(|e| std::io::Error::other(format!("Setting HEAD failed: {e}")))?;
+
+    let mut remote = repo
+        .find_remote(&remote_name)
+        .map_err(|e| std::io::Error::other(format!("Finding remote failed: {e}")))?;
+    // Notes already on the remote are merged in, the push would be rejected otherwise.
+    if let Some(fetched) = fetch_existing(&repo, &mut remote, &remote_name, &branch)? {
+        let message = format!("Merge {remote_name}/{branch}");
+        if let PullOutcome::Conflicts(paths) = merge(&repo, &fetched, &message)? {
+            return Err(std::io::Error::other(format!(
+                "The remote already has notes, conflicts in {}. Resolve the conflict markers, then run grom sync push.",
+                paths.join(", ")
+            )));
+        }
+    }
+    let callbacks = remote_callbacks();
     let mut push_options = PushOptions::new();
     push_options.remote_callbacks(callbacks);
 
     remote
-        .push(
-            &["refs/heads/main:refs/heads/main"],
-            Some(&mut push_options),
-        )
-        .map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Pushing changes failed: {e}"),
-            )
-        })
+        .push(&[format!("{refname}:{refname}")], Some(&mut push_options))
+        .map_err(|e| std::io::Error::other(format!("Pushing changes failed: {e}")))
 }
-pub fn push_changes(path: String, message: String) -> Result<(), std::io::Error> {
-    let repo: Repository = Repository::open(path).map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Opening repository failed: {e}"),
-        )
-    })?;
 
-    let mut index = repo.index().map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Getting index failed: {e}"),
+// Fetches `branch` into its remote-tracking branch. None when the remote does not have it.
+fn fetch_existing<'r>(
+    repo: &'r Repository,
+    remote: &mut git2::Remote,
+    remote_name: &str,
+    branch: &str,
+) -> Result<Option<AnnotatedCommit<'r>>, std::io::Error> {
+    let tracking = format!("refs/remotes/{remote_name}/{branch}");
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(remote_callbacks());
+    remote
+        .fetch(
+            &[format!("+refs/heads/{branch}:{tracking}")],
+            Some(&mut fetch_options),
+            None,
         )
-    })?;
+        .map_err(|e| std::io::Error::other(format!("Fetching changes failed: {e}")))?;
+    match repo.find_reference(&tracking) {
+        Ok(reference) => repo
+            .reference_to_annotated_commit(&reference)
+            .map(Some)
+            .map_err(|e| {
+                std::io::Error::other(format!("Converting to annotated commit failed: {e}"))
+            }),
+        Err(_) => Ok(None),
+    }
+}
+
+pub fn push_changes(
+    path: String,
+    message: String,
+    remote_name: String,
+    branch: Option<String>,
+) -> Result<(), std::io::Error> {
+    let repo: Repository = Repository::open(path)
+        .map_err(|e| std::io::Error::other(format!("Opening repository failed: {e}")))?;
+    let refname = format!("refs/heads/{}", sync_branch(&repo, branch.as_deref())?);
+
+    let mut index = repo
+        .index()
+        .map_err(|e| std::io::Error::other(format!("Getting index failed: {e}")))?;
+    let conflicted = conflicted_paths(&index);
+    let workdir = repo.workdir().unwrap_or(repo.path()).to_path_buf();
     index
         .add_all(["*"].iter(), IndexAddOption::DEFAULT, None)
-        .map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Adding files to index failed: {e}"),
-            )
-        })?;
-    index.write().map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Writing index failed: {e}"),
-        )
-    })?;
+        .map_err(|e| std::io::Error::other(format!("Adding files to index failed: {e}")))?;
+    // add_all leaves deleted notes in the index, a conflict resolved by deleting the note too.
+    index
+        .update_all(["*"].iter(), None)
+        .map_err(|e| std::io::Error::other(format!("Adding files to index failed: {e}")))?;
+    for path in conflicted
+        .iter()
+        .filter(|path| !workdir.join(path).exists())
+    {
+        index
+            .remove_path(std::path::Path::new(path))
+            .map_err(|e| std::io::Error::other(format!("Removing files from index failed: {e}")))?;
+    }
+    if let Some(unresolved) = conflicted.iter().find(|path| {
+        std::fs::read_to_string(workdir.join(path))
+            .is_ok_and(|content| has_conflict_markers(&content))
+    }) {
+        return Err(std::io::Error::other(format!(
+            "{unresolved} still contains conflict markers."
+        )));
+    }
+    index
+        .write()
+        .map_err(|e| std::io::Error::other(format!("Writing index failed: {e}")))?;
 
-    let tree_id = index.write_tree().map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Writing tree failed: {e}"),
-        )
-    })?;
-    let tree = repo.find_tree(tree_id).map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Finding tree failed: {e}"),
-        )
-    })?;
+    let tree_id = index
+        .write_tree()
+        .map_err(|e| std::io::Error::other(format!("Writing tree failed: {e}")))?;
+    let tree = repo
+        .find_tree(tree_id)
+        .map_err(|e| std::io::Error::other(format!("Finding tree failed: {e}")))?;
 
-    let signature = repo.signature().map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Creating signature failed: {e}"),
-        )
-    })?;
+    let signature = repo
+        .signature()
+        .map_err(|e| std::io::Error::other(format!("Creating signature failed: {e}")))?;
     let head = repo
         .head()
-        .map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Getting HEAD failed: {e}"),
-            )
-        })?
+        .map_err(|e| std::io::Error::other(format!("Getting HEAD failed: {e}")))?
         .peel_to_commit()
-        .map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Peeling to commit failed: {e}"),
-            )
-        })?;
+        .map_err(|e| std::io::Error::other(format!("Peeling to commit failed: {e}")))?;
+    // Finishing a pull that stopped on conflicts records the merge.
+    let mut parents = vec![head];
+    if repo.state() == RepositoryState::Merge {
+        parents.push(
+            repo.find_reference("MERGE_HEAD")
+                .and_then(|r| r.peel_to_commit())
+                .map_err(|e| std::io::Error::other(format!("Finding MERGE_HEAD failed: {e}")))?,
+        );
+    }
     repo.commit(
         Some("HEAD"),
         &signature,
         &signature,
         &message,
         &tree,
-        &[&head],
+        &parents.iter().collect::<Vec<_>>(),
     )
-    .map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Committing changes failed: {e}"),
-        )
-    })?;
+    .map_err(|e| std::io::Error::other(format!("Committing changes failed: {e}")))?;
+    repo.cleanup_state()
+        .map_err(|e| std::io::Error::other(format!("Cleaning up merge state failed: {e}")))?;
 
-    let mut remote = repo.find_remote("origin").map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Finding remote failed: {e}"),
-        )
-    })?;
-    let mut callbacks = git2::RemoteCallbacks::new();
-
-    let mut auth_attempts = 0;
-    let max_auth_attempts = 1;
-
-    callbacks.credentials(move |url, username_from_url, _| {
-        if auth_attempts >= max_auth_attempts {
-            return Err(git2::Error::from_str(
-                "Maximum authentication attempts exceeded.",
-            ));
-        }
-        auth_attempts += 1;
-        if url.starts_with("ssh://") || url.starts_with("git@") {
-            match git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git")) {
-                Ok(cred) => Ok(cred),
-                Err(_) => {
-                    let username: String = cliclack::input("Enter your Git Username.")
-                        .interact()
-                        .unwrap();
-                    let password: String = cliclack::input("Enter your Git Password.")
-                        .interact()
-                        .unwrap();
-                    match git2::Cred::userpass_plaintext(username.as_str(), password.as_str()) {
-                        Ok(cred) => Ok(cred),
-                        Err(e) => Err(e),
-                    }
-                }
-            }
-        } else {
-            let username: String = cliclack::input("Enter your Git Username.")
-                .interact()
-                .unwrap();
-            let password: String = cliclack::input("Enter your Git Password.")
-                .interact()
-                .unwrap();
-            match git2::Cred::userpass_plaintext(username.as_str(), password.as_str()) {
-                Ok(cred) => Ok(cred),
-                Err(e) => Err(e),
-            }
-        }
-    });
+    let mut remote = repo
+        .find_remote(&remote_name)
+        .map_err(|e| std::io::Error::other(format!("Finding remote failed: {e}")))?;
+    let callbacks = remote_callbacks();
     let mut push_options = PushOptions::new();
     push_options.remote_callbacks(callbacks);
 
     remote
-        .push(
-            &["refs/heads/main:refs/heads/main"],
-            Some(&mut push_options),
-        )
-        .map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Pushing changes failed: {e}"),
-            )
-        })
+        .push(&[format!("{refname}:{refname}")], Some(&mut push_options))
+        .map_err(|e| std::io::Error::other(format!("Pushing changes failed: {e}")))
 }
 
-pub fn pull_changes(path: String) -> Result<(), std::io::Error> {
-    let repo = Repository::open(path).map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Opening repository failed: {e}"),
-        )
-    })?;
+fn conflicted_paths(index: &git2::Index) -> Vec<String> {
+    let Ok(conflicts) = index.conflicts() else {
+        return Vec::new();
+    };
+    conflicts
+        .filter_map(Result::ok)
+        .filter_map(|c| c.our.or(c.their).or(c.ancestor))
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .collect()
+}
 
-    let mut remote = repo.find_remote("origin").map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Finding remote failed: {e}"),
-        )
-    })?;
-    let mut remote_callbacks = RemoteCallbacks::new();
+fn has_conflict_markers(content: &str) -> bool {
+    content.lines().any(|l| l.starts_with("<<<<<<< "))
+        && content.lines().any(|l| l.starts_with(">>>>>>> "))
+}
 
-    let mut auth_attempts = 0;
-    let max_auth_attempts = 1;
+// Only tracked files count, new notes do not get in the way of a merge.
+fn has_local_changes(repo: &Repository) -> Result<bool, std::io::Error> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    repo.statuses(Some(&mut options))
+        .map(|statuses| !statuses.is_empty())
+        .map_err(|e| std::io::Error::other(format!("Reading status failed: {e}")))
+}
 
-    remote_callbacks.credentials(move |url, username_from_url, _| {
-        if auth_attempts >= max_auth_attempts {
-            return Err(git2::Error::from_str(
-                "Maximum authentication attempts exceeded.",
-            ));
+// Notes that both sides only appended to, or added list items to, are merged without
+// conflict markers and staged.
+fn resolve_notes(repo: &Repository, index: &mut git2::Index) -> Result<(), std::io::Error> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| std::io::Error::other("Repository has no working directory."))?;
+    let conflicts: Vec<git2::IndexConflict> = index
+        .conflicts()
+        .map_err(|e| std::io::Error::other(format!("Reading conflicts failed: {e}")))?
+        .filter_map(Result::ok)
+        .collect();
+    let content = |entry: &git2::IndexEntry| {
+        repo.find_blob(entry.id)
+            .ok()
+            .and_then(|blob| String::from_utf8(blob.content().to_vec()).ok())
+    };
+    for conflict in conflicts {
+        let (Some(our), Some(their)) = (conflict.our, conflict.their) else {
+            continue;
+        };
+        let path = String::from_utf8_lossy(&our.path).to_string();
+        if !path.ends_with(".md") {
+            continue;
         }
-        auth_attempts += 1;
-        if url.starts_with("ssh://") || url.starts_with("git@") {
-            match git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git")) {
-                Ok(cred) => Ok(cred),
-                Err(_) => {
-                    let username: String = cliclack::input("Enter your Git Username.")
-                        .interact()
-                        .unwrap();
-                    let password: String = cliclack::input("Enter your Git Password.")
-                        .interact()
-                        .unwrap();
-                    match git2::Cred::userpass_plaintext(username.as_str(), password.as_str()) {
-                        Ok(cred) => Ok(cred),
-                        Err(e) => Err(e),
-                    }
-                }
-            }
-        } else {
-            let username: String = cliclack::input("Enter your Git Username.")
-                .interact()
-                .unwrap();
-            let password: String = cliclack::input("Enter your Git Password.")
-                .interact()
-                .unwrap();
-            match git2::Cred::userpass_plaintext(username.as_str(), password.as_str()) {
-                Ok(cred) => Ok(cred),
-                Err(e) => Err(e),
-            }
+        let base = match &conflict.ancestor {
+            Some(ancestor) => match content(ancestor) {
+                Some(base) => Some(base),
+                None => continue,
+            },
+            None => None,
+        };
+        let (Some(ours), Some(theirs)) = (content(&our), content(&their)) else {
+            continue;
+        };
+        if let Some(merged) = notemerge::merge(base.as_deref(), &ours, &theirs) {
+            std::fs::write(workdir.join(&path), merged)?;
+            index
+                .add_path(std::path::Path::new(&path))
+                .map_err(|e| std::io::Error::other(format!("Adding files to index failed: {e}")))?;
         }
-    });
+    }
+    index
+        .write()
+        .map_err(|e| std::io::Error::other(format!("Writing index failed: {e}")))
+}
+
+fn merge(
+    repo: &Repository,
+    fetch_commit: &AnnotatedCommit,
+    message: &str,
+) -> Result<PullOutcome, std::io::Error> {
+    let mut checkout = CheckoutBuilder::new();
+    checkout
+        .safe()
+        .allow_conflicts(true)
+        .conflict_style_merge(true);
+    repo.merge(&[fetch_commit], None, Some(&mut checkout))
+        .map_err(|e| std::io::Error::other(format!("Merging failed: {e}")))?;
+
+    let mut index = repo
+        .index()
+        .map_err(|e| std::io::Error::other(format!("Getting index failed: {e}")))?;
+    if index.has_conflicts() {
+        resolve_notes(repo, &mut index)?;
+    }
+    if index.has_conflicts() {
+        // The repository stays in the merging state, push records the merge once resolved.
+        return Ok(PullOutcome::Conflicts(conflicted_paths(&index)));
+    }
 
+    let tree = index
+        .write_tree()
+        .and_then(|id| repo.find_tree(id))
+        .map_err(|e| std::io::Error::other(format!("Writing tree failed: {e}")))?;
+    let signature = repo
+        .signature()
+        .map_err(|e| std::io::Error::other(format!("Creating signature failed: {e}")))?;
+    let local = repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+        .map_err(|e| std::io::Error::other(format!("Getting HEAD failed: {e}")))?;
+    let remote = repo
+        .find_commit(fetch_commit.id())
+        .map_err(|e| std::io::Error::other(format!("Finding fetched commit failed: {e}")))?;
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &[&local, &remote],
+    )
+    .map_err(|e| std::io::Error::other(format!("Committing merge failed: {e}")))?;
+    repo.cleanup_state()
+        .map_err(|e| std::io::Error::other(format!("Cleaning up merge state failed: {e}")))?;
+    Ok(PullOutcome::Merged)
+}
+
+// Replays the local commits on top of the fetched ones. Returns false, with the rebase
+// aborted, when a commit does not apply cleanly.
+fn rebase(repo: &Repository, fetch_commit: &AnnotatedCommit) -> Result<bool, git2::Error> {
+    let head = repo.reference_to_annotated_commit(&repo.head()?)?;
+    let mut rebase = repo.rebase(Some(&head), Some(fetch_commit), None, None)?;
+    let signature = repo.signature()?;
+    while let Some(operation) = rebase.next() {
+        operation?;
+        if repo.index()?.has_conflicts() {
+            rebase.abort()?;
+            return Ok(false);
+        }
+        match rebase.commit(None, &signature, None) {
+            Ok(_) => {}
+            // The remote already has this change.
+            Err(e) if e.code() == git2::ErrorCode::Applied => {}
+            Err(e) => return Err(e),
+        }
+    }
+    rebase.finish(Some(&signature))?;
+    Ok(true)
+}
+
+pub fn pull_changes(
+    path: String,
+    remote_name: String,
+    branch: Option<String>,
+    rebase_local: bool,
+) -> Result<PullOutcome, std::io::Error> {
+    let repo = Repository::open(path)
+        .map_err(|e| std::io::Error::other(format!("Opening repository failed: {e}")))?;
+    let branch = sync_branch(&repo, branch.as_deref())?;
+    if repo.state() != RepositoryState::Clean {
+        return Err(std::io::Error::other(
+            "A previous pull is unfinished, resolve the conflicts and push first.",
+        ));
+    }
+
+    let mut remote = repo
+        .find_remote(&remote_name)
+        .map_err(|e| std::io::Error::other(format!("Finding remote failed: {e}")))?;
     let mut fetch_options = FetchOptions::new();
-    fetch_options.remote_callbacks(remote_callbacks);
+    fetch_options.remote_callbacks(remote_callbacks());
 
     remote
-        .fetch(&["main"], Some(&mut fetch_options), None)
-        .map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Fetching changes failed: {e}"),
-            )
-        })?;
+        .fetch(&[&branch], Some(&mut fetch_options), None)
+        .map_err(|e| std::io::Error::other(format!("Fetching changes failed: {e}")))?;
 
-    let fetch_head = repo.find_reference("FETCH_HEAD").map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Finding FETCH_HEAD failed: {e}"),
-        )
-    })?;
+    let fetch_head = repo
+        .find_reference("FETCH_HEAD")
+        .map_err(|e| std::io::Error::other(format!("Finding FETCH_HEAD failed: {e}")))?;
     let fetch_commit = repo
         .reference_to_annotated_commit(&fetch_head)
         .map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Converting to annotated commit failed: {e}"),
-            )
+            std::io::Error::other(format!("Converting to annotated commit failed: {e}"))
         })?;
 
-    let analysis = repo.merge_analysis(&[&fetch_commit]).map_err(|e| {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Analyzing merge failed: {e}"),
-        )
-    })?;
+    let analysis = repo
+        .merge_analysis(&[&fetch_commit])
+        .map_err(|e| std::io::Error::other(format!("Analyzing merge failed: {e}")))?;
+    if analysis.0.is_up_to_date() {
+        return Ok(PullOutcome::UpToDate);
+    }
+    if !analysis.0.is_unborn() && has_local_changes(&repo)? {
+        return Err(std::io::Error::other(
+            "Notes have uncommitted changes, push them first.",
+        ));
+    }
     if analysis.0.is_fast_forward() {
-        let refname = format!("refs/heads/{}", "main");
+        let refname = format!("refs/heads/{}", branch);
         match repo.find_reference(&refname) {
             Ok(mut r) => {
-                r.set_target(fetch_commit.id(), "Fast-Forward")
-                    .map_err(|e| {
-                        std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            format!("Setting target failed: {e}"),
-                        )
-                    })?;
-                repo.set_head(&refname).map_err(|e| {
-                    std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("Setting HEAD failed: {e}"),
-                    )
+                // Checking out before moving the branch refuses to overwrite untracked notes.
+                let target = repo.find_object(fetch_commit.id(), None).map_err(|e| {
+                    std::io::Error::other(format!("Finding fetched commit failed: {e}"))
                 })?;
-                repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+                repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))
                     .map_err(|e| {
-                        std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            format!("Checking out HEAD failed: {e}"),
-                        )
+                        std::io::Error::other(format!("Checking out changes failed: {e}"))
                     })?;
+                r.set_target(fetch_commit.id(), "Fast-Forward")
+                    .map_err(|e| std::io::Error::other(format!("Setting target failed: {e}")))?;
+                repo.set_head(&refname)
+                    .map_err(|e| std::io::Error::other(format!("Setting HEAD failed: {e}")))?;
             }
             Err(_) => {
                 repo.reference(&refname, fetch_commit.id(), true, "Setting reference")
-                    .map_err(|e| {
-                        std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            format!("Setting reference failed: {e}"),
-                        )
-                    })?;
-                repo.set_head(&refname).map_err(|e| {
-                    std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("Setting HEAD failed: {e}"),
-                    )
-                })?;
+                    .map_err(|e| std::io::Error::other(format!("Setting reference failed: {e}")))?;
+                repo.set_head(&refname)
+                    .map_err(|e| std::io::Error::other(format!("Setting HEAD failed: {e}")))?;
                 repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
-                    .map_err(|e| {
-                        std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            format!("Checking out HEAD failed: {e}"),
-                        )
-                    })?;
+                    .map_err(|e| std::io::Error::other(format!("Checking out HEAD failed: {e}")))?;
             }
         }
-    } else {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::Unsupported,
-            "Non-fast-forward merge not supported.",
-        ));
+        return Ok(PullOutcome::FastForward);
     }
-    Ok(())
+
+    let message = format!("Merge {remote_name}/{branch}");
+    // A rebase that runs into conflicts falls back to a merge, which push can finish.
+    if rebase_local
+        && rebase(&repo, &fetch_commit)
+            .map_err(|e| std::io::Error::other(format!("Rebasing failed: {e}")))?
+    {
+        return Ok(PullOutcome::Rebased);
+    }
+    merge(&repo, &fetch_commit, &message)
 }
@@ -0,0 +1,579 @@
+use crate::core::markdown;
+use crate::core::notes::{DiaryPeriod, NoteKind, Notebook};
+use crate::core::utils;
+use chrono::{Datelike, NaiveDate};
+use pulldown_cmark::{CowStr, Event, LinkType, Options, Parser, Tag, TagEnd};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::{fs, io};
+
+const STYLE: &str = r#"body { font-family: system-ui, sans-serif; max-width: 48rem; margin: 0 auto; padding: 1rem; line-height: 1.5; color: #222; }
+nav { display: flex; gap: 1rem; align-items: center; border-bottom: 1px solid #ddd; padding-bottom: .5rem; margin-bottom: 1rem; }
+nav input { margin-left: auto; padding: .25rem .5rem; }
+a { color: #0b5cad; }
+pre, code { background: #f4f4f4; }
+pre { padding: .5rem; overflow-x: auto; }
+.meta, .backlinks { color: #666; font-size: .9rem; }
+.tag { margin-right: .5rem; }
+table.calendar { border-collapse: collapse; margin-bottom: 1.5rem; }
+table.calendar td, table.calendar th { width: 2.5rem; text-align: center; padding: .25rem; }
+#results { list-style: none; padding: 0; }
+"#;
+
+const SEARCH: &str = r#"(function () {
+  var input = document.getElementById("search");
+  var results = document.getElementById("results");
+  if (!input || !results || typeof GROM_INDEX === "undefined") return;
+  input.addEventListener("input", function () {
+    var terms = input.value.toLowerCase().split(/\s+/).filter(Boolean);
+    results.innerHTML = "";
+    if (terms.length === 0) return;
+    GROM_INDEX.filter(function (page) {
+      var haystack = (page.title + " " + page.tags.join(" ") + " " + page.text).toLowerCase();
+      return terms.every(function (t) { return haystack.indexOf(t) !== -1; });
+    }).slice(0, 20).forEach(function (page) {
+      var item = document.createElement("li");
+      var link = document.createElement("a");
+      link.href = GROM_ROOT + page.url;
+      link.textContent = page.title;
+      item.appendChild(link);
+      results.appendChild(item);
+    });
+  });
+})();
+"#;
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn page_path(rel_path: &str) -> String {
+    format!("{}.html", rel_path.trim_end_matches(".md"))
+}
+
+// Prefix leading from a page back to the site root.
+pub fn root(page: &str) -> String {
+    "../".repeat(page.matches('/').count())
+}
+
+fn is_external(url: &str) -> bool {
+    url.contains("://") || url.starts_with("mailto:") || url.starts_with('#')
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_WIKILINKS
+}
+
+pub struct Rendered {
+    pub html: String,
+    pub assets: Vec<String>,
+}
+
+/// Renders a note's body. `target` maps a linked note to the url of its page, links it
+/// returns `None` for are rendered as plain text. Assets are returned as paths relative to
+/// the notebook root; their urls are kept unless `asset` maps such a path to a new url.
+pub fn render(
+    notebook: &Notebook,
+    index: usize,
+    target: &dyn Fn(usize) -> Option<String>,
+    asset: Option<&dyn Fn(&str) -> String>,
+) -> Rendered {
+    let note = &notebook.notes[index];
+    let base = Path::new(&note.rel_path).parent().unwrap_or(Path::new(""));
+    let mut assets = Vec::new();
+    let mut dropped: Vec<bool> = Vec::new();
+    let mut events = Vec::new();
+
+    for event in Parser::new_ext(note.body(), options()) {
+        match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                if is_external(&dest_url) {
+                    dropped.push(false);
+                    events.push(Event::Start(Tag::Link {
+                        link_type,
+                        dest_url,
+                        title,
+                        id,
+                    }));
+                    continue;
+                }
+                let (path, fragment) = match dest_url.split_once('#') {
+                    Some((path, fragment)) => (path.to_string(), format!("#{}", fragment)),
+                    None => (dest_url.to_string(), String::new()),
+                };
+                let wiki = matches!(link_type, LinkType::WikiLink { .. });
+                let link = markdown::Link {
+                    target: path.clone(),
+                    wiki,
+                };
+                let url = match notebook.resolve(index, &link) {
+                    Some(linked) => target(linked).map(|url| format!("{}{}", url, fragment)),
+                    None if !wiki && !path.ends_with(".md") => {
+                        let path = normalize(&base.join(&path));
+                        let url = match asset {
+                            Some(asset) => asset(&path),
+                            None => dest_url.to_string(),
+                        };
+                        assets.push(path);
+                        Some(url)
+                    }
+                    None => None,
+                };
+                match url {
+                    Some(url) => {
+                        dropped.push(false);
+                        events.push(Event::Start(Tag::Link {
+                            link_type: LinkType::Inline,
+                            dest_url: CowStr::from(url),
+                            title,
+                            id,
+                        }));
+                    }
+                    None => dropped.push(true),
+                }
+            }
+            Event::End(TagEnd::Link) => {
+                if !dropped.pop().unwrap_or(false) {
+                    events.push(Event::End(TagEnd::Link));
+                }
+            }
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let mut dest_url = dest_url;
+                if !is_external(&dest_url) {
+                    let path = normalize(&base.join(dest_url.as_ref()));
+                    if let Some(asset) = asset {
+                        dest_url = CowStr::from(asset(&path));
+                    }
+                    assets.push(path);
+                }
+                events.push(Event::Start(Tag::Image {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }));
+            }
+            event => events.push(event),
+        }
+    }
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    Rendered { html, assets }
+}
+
+fn normalize(path: &Path) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                parts.pop();
+            }
+            std::path::Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            _ => {}
+        }
+    }
+    parts.join("/")
+}
+
+pub fn plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new_ext(markdown, options()) {
+        match event {
+            Event::Text(t) | Event::Code(t) => {
+                text.push_str(&t);
+                text.push(' ');
+            }
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Tags that slugify alike (`c++` and `c`) get a numeric suffix, in tag order, so every tag
+// keeps a page of its own; `index` is taken by the tag index.
+fn tag_pages<'a>(tags: impl Iterator<Item = &'a String>) -> BTreeMap<String, String> {
+    let tags: BTreeSet<&String> = tags.collect();
+    let mut used: BTreeSet<String> = BTreeSet::from(["index".to_string()]);
+    let mut pages = BTreeMap::new();
+    for tag in tags {
+        let base = match utils::slugify(tag) {
+            slug if slug.is_empty() => "tag".to_string(),
+            slug => slug,
+        };
+        let mut slug = base.clone();
+        let mut count = 1;
+        while !used.insert(slug.clone()) {
+            count += 1;
+            slug = format!("{}-{}", base, count);
+        }
+        pages.insert(tag.clone(), format!("tags/{}.html", slug));
+    }
+    pages
+}
+
+fn layout(title: &str, page: &str, body: &str) -> String {
+    let prefix = root(page);
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<link rel="stylesheet" href="{prefix}style.css">
+</head>
+<body>
+<nav><a href="{prefix}index.html">Home</a><a href="{prefix}diary.html">Diary</a><a href="{prefix}tags/index.html">Tags</a><input id="search" type="search" placeholder="Search"></nav>
+<ul id="results"></ul>
+<main>
+{body}
+</main>
+<script>var GROM_ROOT = "{prefix}";</script>
+<script src="{prefix}search-index.js"></script>
+<script src="{prefix}search.js"></script>
+</body>
+</html>
+"#,
+        title = escape(title),
+        prefix = prefix,
+        body = body
+    )
+}
+
+/// A single self-contained page, used for documents outside of the site export.
+pub fn standalone(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+{style}</style>
+</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        title = escape(title),
+        style = STYLE,
+        body = body
+    )
+}
+
+/// Renders markdown that is not a note of the notebook, e.g. a compiled document.
+/// Headings can carry explicit ids with `{#id}`.
+pub fn render_markdown(markdown: &str) -> String {
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(
+        &mut html,
+        Parser::new_ext(markdown, options() | Options::ENABLE_HEADING_ATTRIBUTES),
+    );
+    html
+}
+
+fn write(out_dir: &Path, page: &str, content: &str) -> Result<(), io::Error> {
+    let path = out_dir.join(page);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)
+}
+
+fn link(from: &str, to: &str, text: &str) -> String {
+    format!("<a href=\"{}{}\">{}</a>", root(from), to, escape(text))
+}
+
+fn note_list(from: &str, notebook: &Notebook, notes: &[usize]) -> String {
+    let mut html = String::from("<ul>\n");
+    for i in notes {
+        let note = &notebook.notes[*i];
+        html.push_str(&format!(
+            "<li>{}</li>\n",
+            link(from, &page_path(&note.rel_path), &note.title())
+        ));
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+#[derive(Serialize)]
+struct SearchEntry {
+    title: String,
+    url: String,
+    tags: Vec<String>,
+    text: String,
+}
+
+fn calendar(page: &str, notebook: &Notebook, included: &[usize]) -> String {
+    let mut months: BTreeMap<(i32, u32), BTreeMap<u32, usize>> = BTreeMap::new();
+    let mut periods: BTreeMap<(i32, u32), Vec<usize>> = BTreeMap::new();
+    for i in included {
+        let note = &notebook.notes[*i];
+        let Some(date) = note.date else {
+            continue;
+        };
+        let key = (date.year(), date.month());
+        match note.period {
+            Some(DiaryPeriod::Daily) => {
+                months.entry(key).or_default().insert(date.day(), *i);
+            }
+            Some(_) => {
+                months.entry(key).or_default();
+                periods.entry(key).or_default().push(*i);
+            }
+            None => {}
+        }
+    }
+
+    let mut html = String::from("<h1>Diary</h1>\n");
+    for ((year, month), days) in months.iter().rev() {
+        let Some(first) = NaiveDate::from_ymd_opt(*year, *month, 1) else {
+            continue;
+        };
+        html.push_str(&format!("<h2>{}</h2>\n", first.format("%B %Y")));
+        if let Some(notes) = periods.get(&(*year, *month)) {
+            html.push_str("<ul>\n");
+            for i in notes {
+                let note = &notebook.notes[*i];
+                let label = match (&note.meta.title, note.period) {
+                    (Some(title), _) => title.clone(),
+                    (None, Some(DiaryPeriod::Weekly)) => {
+                        format!(
+                            "Week {}",
+                            note.date.map(|d| d.iso_week().week()).unwrap_or(0)
+                        )
+                    }
+                    _ => first.format("%B %Y").to_string(),
+                };
+                html.push_str(&format!(
+                    "<li>{}</li>\n",
+                    link(page, &page_path(&note.rel_path), &label)
+                ));
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("<table class=\"calendar\">\n<tr><th>Mo</th><th>Tu</th><th>We</th><th>Th</th><th>Fr</th><th>Sa</th><th>Su</th></tr>\n<tr>");
+        let offset = first.weekday().num_days_from_monday();
+        for _ in 0..offset {
+            html.push_str("<td></td>");
+        }
+        let mut day = first;
+        while day.month() == *month {
+            let cell = match days.get(&day.day()) {
+                Some(i) => link(
+                    page,
+                    &page_path(&notebook.notes[*i].rel_path),
+                    &day.day().to_string(),
+                ),
+                None => day.day().to_string(),
+            };
+            html.push_str(&format!("<td>{}</td>", cell));
+            match day.succ_opt() {
+                Some(next) => {
+                    if next.weekday() == chrono::Weekday::Mon && next.month() == *month {
+                        html.push_str("</tr>\n<tr>");
+                    }
+                    day = next
+                }
+                None => break,
+            }
+        }
+        html.push_str("</tr>\n</table>\n");
+    }
+    html
+}
+
+/// Writes a static site for the notes in `included` to `out_dir`. Links to notes outside
+/// of it are rendered as plain text. Returns the number of note pages written.
+pub fn write_site(
+    notebook: &Notebook,
+    included: &[usize],
+    out_dir: &Path,
+    title: &str,
+) -> Result<usize, io::Error> {
+    fs::create_dir_all(out_dir)?;
+    let selected: BTreeSet<usize> = included.iter().copied().collect();
+
+    let mut backlinks: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    for from in included {
+        for link in notebook.notes[*from].links() {
+            if let Some(to) = notebook.resolve(*from, &link) {
+                if to != *from && selected.contains(&to) {
+                    backlinks.entry(to).or_default().insert(*from);
+                }
+            }
+        }
+    }
+
+    let note_tags: Vec<Vec<String>> = included.iter().map(|i| notebook.notes[*i].tags()).collect();
+    let tag_page = tag_pages(note_tags.iter().flatten());
+    let mut tags: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    let mut assets: BTreeSet<String> = BTreeSet::new();
+    let mut search = Vec::new();
+    for (i, note_tags) in included.iter().zip(note_tags) {
+        let note = &notebook.notes[*i];
+        let page = page_path(&note.rel_path);
+        let prefix = root(&note.rel_path);
+        let target = |j: usize| {
+            selected
+                .contains(&j)
+                .then(|| format!("{}{}", prefix, page_path(&notebook.notes[j].rel_path)))
+        };
+        let rendered = render(notebook, *i, &target, None);
+        assets.extend(rendered.assets);
+
+        let mut meta = Vec::new();
+        if let Some(project) = &note.project {
+            if let Some(start) = notebook
+                .project_start(project)
+                .filter(|s| selected.contains(s))
+            {
+                meta.push(link(
+                    &page,
+                    &page_path(&notebook.notes[start].rel_path),
+                    project,
+                ));
+            }
+        }
+        if let Some(date) = note.date.or(note.created().map(|c| c.date())) {
+            meta.push(date.format("%Y-%m-%d").to_string());
+        }
+        for tag in &note_tags {
+            tags.entry(tag.clone()).or_default().push(*i);
+            meta.push(format!(
+                "<span class=\"tag\">{}</span>",
+                link(&page, &tag_page[tag], &format!("#{}", tag))
+            ));
+        }
+
+        let mut body = String::new();
+        if !meta.is_empty() {
+            body.push_str(&format!("<p class=\"meta\">{}</p>\n", meta.join(" · ")));
+        }
+        body.push_str(&rendered.html);
+        if let Some(sources) = backlinks.get(i) {
+            let sources: Vec<usize> = sources.iter().copied().collect();
+            body.push_str("<section class=\"backlinks\">\n<h2>Backlinks</h2>\n");
+            body.push_str(&note_list(&page, notebook, &sources));
+            body.push_str("</section>\n");
+        }
+        write(out_dir, &page, &layout(&note.title(), &page, &body))?;
+
+        search.push(SearchEntry {
+            title: note.title(),
+            url: page,
+            tags: note_tags,
+            text: plain_text(note.body()),
+        });
+    }
+
+    let mut index = format!("<h1>{}</h1>\n", escape(title));
+    let projects: BTreeSet<&String> = included
+        .iter()
+        .filter_map(|i| notebook.notes[*i].project.as_ref())
+        .collect();
+    if !projects.is_empty() {
+        index.push_str("<h2>Projects</h2>\n");
+        for project in projects {
+            let mut notes: Vec<usize> = included
+                .iter()
+                .copied()
+                .filter(|i| notebook.notes[*i].project.as_ref() == Some(project))
+                .collect();
+            notes.sort_by_key(|i| !notebook.notes[*i].is_project_start());
+            let heading = match notes.first() {
+                Some(start) if notebook.notes[*start].is_project_start() => link(
+                    "index.html",
+                    &page_path(&notebook.notes[*start].rel_path),
+                    &notebook.notes[*start].title(),
+                ),
+                _ => escape(project),
+            };
+            index.push_str(&format!("<h3>{}</h3>\n", heading));
+            index.push_str(&note_list("index.html", notebook, &notes[..]));
+        }
+    }
+    for (kind, heading) in [(NoteKind::Quick, "Quick notes"), (NoteKind::Other, "Notes")] {
+        let notes: Vec<usize> = included
+            .iter()
+            .copied()
+            .filter(|i| notebook.notes[*i].kind == kind)
+            .collect();
+        if !notes.is_empty() {
+            index.push_str(&format!("<h2>{}</h2>\n", heading));
+            index.push_str(&note_list("index.html", notebook, &notes));
+        }
+    }
+    write(out_dir, "index.html", &layout(title, "index.html", &index))?;
+
+    let diary = calendar("diary.html", notebook, included);
+    write(
+        out_dir,
+        "diary.html",
+        &layout("Diary", "diary.html", &diary),
+    )?;
+
+    let mut tag_index = String::from("<h1>Tags</h1>\n<ul>\n");
+    for (tag, notes) in &tags {
+        let page = &tag_page[tag];
+        tag_index.push_str(&format!(
+            "<li>{} ({})</li>\n",
+            link("tags/index.html", page, &format!("#{}", tag)),
+            notes.len()
+        ));
+        let body = format!(
+            "<h1>#{}</h1>\n{}",
+            escape(tag),
+            note_list(page, notebook, notes)
+        );
+        write(out_dir, page, &layout(&format!("#{}", tag), page, &body))?;
+    }
+    tag_index.push_str("</ul>\n");
+    write(
+        out_dir,
+        "tags/index.html",
+        &layout("Tags", "tags/index.html", &tag_index),
+    )?;
+
+    write(out_dir, "style.css", STYLE)?;
+    write(out_dir, "search.js", SEARCH)?;
+    write(
+        out_dir,
+        "search-index.js",
+        &format!("var GROM_INDEX = {};\n", serde_json::to_string(&search)?),
+    )?;
+
+    for asset in assets {
+        let source = notebook.root.join(&asset);
+        if source.is_file() {
+            let dest = out_dir.join(&asset);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(source, dest)?;
+        }
+    }
+    Ok(included.len())
+}
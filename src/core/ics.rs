@@ -0,0 +1,404 @@
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+use std::collections::HashSet;
+
+pub fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Content lines are folded at 75 octets as required by RFC 5545.
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+pub fn date(value: NaiveDate) -> String {
+    value.format("%Y%m%d").to_string()
+}
+
+pub fn datetime(value: NaiveDateTime) -> String {
+    value.format("%Y%m%dT%H%M%S").to_string()
+}
+
+pub struct Calendar {
+    lines: Vec<String>,
+}
+
+impl Calendar {
+    pub fn new() -> Calendar {
+        Calendar {
+            lines: vec![
+                "BEGIN:VCALENDAR".to_string(),
+                "VERSION:2.0".to_string(),
+                "PRODID:-//grom//grom//EN".to_string(),
+                "CALSCALE:GREGORIAN".to_string(),
+            ],
+        }
+    }
+
+    pub fn component(&mut self, name: &str, properties: &[(String, String)]) {
+        self.lines.push(format!("BEGIN:{}", name));
+        for (key, value) in properties {
+            self.lines.push(format!("{}:{}", key, value));
+        }
+        self.lines.push(format!("END:{}", name));
+    }
+
+    pub fn render(mut self) -> String {
+        self.lines.push("END:VCALENDAR".to_string());
+        self.lines.iter().map(|l| fold(l)).collect()
+    }
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Calendar::new()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Event {
+    pub uid: String,
+    pub summary: String,
+    pub start: NaiveDateTime,
+    pub end: Option<NaiveDateTime>,
+    pub all_day: bool,
+    pub location: Option<String>,
+    pub attendees: Vec<String>,
+    pub rrule: Option<String>,
+    // DTSTART in its own time zone, recurring events repeat at that wall-clock time.
+    pub zoned_start: NaiveDateTime,
+    pub zone: Option<chrono_tz::Tz>,
+    pub exdates: Vec<NaiveDateTime>,
+    // Set on a changed instance of a recurring event, the start it replaces.
+    pub recurrence_id: Option<NaiveDateTime>,
+    // A TZID that is not in the time zone database; such times are taken as local time.
+    pub unknown_tz: Option<String>,
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => {}
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        match (
+            line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')),
+            lines.last_mut(),
+        ) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn tzid(params: &str) -> Option<&str> {
+    params
+        .split(';')
+        .find_map(|p| p.strip_prefix("TZID="))
+        .map(|tz| tz.trim_matches('"'))
+}
+
+fn unknown_tz(params: &str) -> Option<String> {
+    tzid(params)
+        .filter(|tz| tz.parse::<chrono_tz::Tz>().is_err())
+        .map(String::from)
+}
+
+fn to_local(time: NaiveDateTime, zone: Option<chrono_tz::Tz>) -> NaiveDateTime {
+    zone.and_then(|tz| tz.from_local_datetime(&time).earliest())
+        .map(|t| t.with_timezone(&chrono::Local).naive_local())
+        .unwrap_or(time)
+}
+
+// A time as written and the zone it is in: UTC, a known TZID, or None for floating times and
+// unknown time zones, which are taken as local wall-clock time.
+fn parse_zoned(params: &str, value: &str) -> Option<(NaiveDateTime, Option<chrono_tz::Tz>, bool)> {
+    if params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME") || value.len() == 8 {
+        let day = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((day.and_hms_opt(0, 0, 0)?, None, true));
+    }
+    match value.strip_suffix('Z') {
+        Some(utc) => {
+            let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+            Some((utc, Some(chrono_tz::UTC), false))
+        }
+        None => {
+            let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+            let zone = tzid(params).and_then(|tz| tz.parse::<chrono_tz::Tz>().ok());
+            Some((time, zone, false))
+        }
+    }
+}
+
+// Converted to local time, and whether it is a date without a time.
+fn parse_datetime(params: &str, value: &str) -> Option<(NaiveDateTime, bool)> {
+    parse_zoned(params, value).map(|(time, zone, all_day)| (to_local(time, zone), all_day))
+}
+
+fn attendee(params: &str, value: &str) -> String {
+    params
+        .split(';')
+        .find_map(|p| p.strip_prefix("CN="))
+        .map(|cn| cn.trim_matches('"').to_string())
+        .unwrap_or_else(|| {
+            value
+                .trim_start_matches("mailto:")
+                .trim_start_matches("MAILTO:")
+                .to_string()
+        })
+}
+
+pub fn parse_events(content: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Event> = None;
+    for line in unfold(content) {
+        if line == "BEGIN:VEVENT" {
+            current = Some(Event::default());
+            continue;
+        }
+        if line == "END:VEVENT" {
+            if let Some(event) = current.take() {
+                if event.start != NaiveDateTime::default() {
+                    events.push(event);
+                }
+            }
+            continue;
+        }
+        let Some(event) = current.as_mut() else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = key.split_once(';').unwrap_or((key, ""));
+        match name {
+            "SUMMARY" => event.summary = unescape(value),
+            "LOCATION" => event.location = Some(unescape(value)),
+            "UID" => event.uid = value.to_string(),
+            "DTSTART" => {
+                if let Some((start, zone, all_day)) = parse_zoned(params, value) {
+                    event.start = to_local(start, zone);
+                    event.zoned_start = start;
+                    event.zone = zone;
+                    event.all_day = all_day;
+                    event.unknown_tz = unknown_tz(params);
+                }
+            }
+            "RRULE" => event.rrule = Some(value.to_string()),
+            "EXDATE" => event.exdates.extend(
+                value
+                    .split(',')
+                    .filter_map(|v| parse_datetime(params, v).map(|(d, _)| d)),
+            ),
+            "RECURRENCE-ID" => event.recurrence_id = parse_datetime(params, value).map(|(d, _)| d),
+            "DTEND" => event.end = parse_datetime(params, value).map(|(end, _)| end),
+            "ATTENDEE" => event.attendees.push(attendee(params, value)),
+            _ => {}
+        }
+    }
+    events
+}
+
+struct Rule {
+    weekly: bool,
+    interval: i64,
+    by_day: Vec<Weekday>,
+    until: Option<NaiveDateTime>,
+    count: Option<usize>,
+}
+
+fn weekday(value: &str) -> Option<Weekday> {
+    match value {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+// Daily and weekly rules with INTERVAL, BYDAY, UNTIL and COUNT are supported.
+fn parse_rule(rule: &str) -> Result<Rule, String> {
+    let mut parsed = Rule {
+        weekly: false,
+        interval: 1,
+        by_day: Vec::new(),
+        until: None,
+        count: None,
+    };
+    let mut freq = None;
+    for part in rule.split(';').filter(|p| !p.is_empty()) {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| format!("malformed rule {}", rule))?;
+        let invalid = || format!("{} is not supported", part);
+        match key {
+            "FREQ" => freq = Some(value),
+            "INTERVAL" => {
+                parsed.interval = value.parse().ok().filter(|i| *i > 0).ok_or_else(invalid)?
+            }
+            "BYDAY" => {
+                for day in value.split(',') {
+                    parsed.by_day.push(weekday(day).ok_or_else(invalid)?);
+                }
+            }
+            "UNTIL" => {
+                let (until, all_day) = parse_datetime("", value).ok_or_else(invalid)?;
+                // A date-only UNTIL includes that day.
+                parsed.until = Some(if all_day {
+                    until.date().and_hms_opt(23, 59, 59).unwrap_or(until)
+                } else {
+                    until
+                });
+            }
+            "COUNT" => parsed.count = Some(value.parse().map_err(|_| invalid())?),
+            "WKST" => {}
+            _ => return Err(invalid()),
+        }
+    }
+    match freq {
+        Some("DAILY") => Ok(parsed),
+        Some("WEEKLY") => Ok(Rule {
+            weekly: true,
+            ..parsed
+        }),
+        Some(other) => Err(format!("FREQ={} is not supported", other)),
+        None => Err(format!("malformed rule {}", rule)),
+    }
+}
+
+fn monday(day: NaiveDate) -> NaiveDate {
+    day - Days::new(day.weekday().num_days_from_monday() as u64)
+}
+
+// Whether a recurring event has an instance on `day`, in the event's own time zone.
+fn occurs(event: &Event, rule: &Rule, day: NaiveDate) -> bool {
+    let first = event.zoned_start.date();
+    if day < first {
+        return false;
+    }
+    // DTSTART is always the first instance, even when it does not match the rule.
+    let matches = |d: NaiveDate| {
+        let weekday = if rule.by_day.is_empty() {
+            !rule.weekly || d.weekday() == first.weekday()
+        } else {
+            rule.by_day.contains(&d.weekday())
+        };
+        let step = if rule.weekly {
+            (monday(d) - monday(first)).num_days() / 7
+        } else {
+            (d - first).num_days()
+        };
+        d == first || (weekday && step % rule.interval == 0)
+    };
+    if !matches(day) {
+        return false;
+    }
+    // Excluded dates still count towards COUNT.
+    rule.count.is_none_or(|count| {
+        first
+            .iter_days()
+            .take_while(|d| *d < day)
+            .filter(|d| matches(*d))
+            .count()
+            < count
+    })
+}
+
+// The local start of the instance of a recurring event on the local `day`, if there is one.
+fn occurrence(event: &Event, rule: &Rule, day: NaiveDate) -> Option<NaiveDateTime> {
+    // The instance can fall on a different date in the event's time zone.
+    let candidates = [day.pred_opt(), Some(day), day.succ_opt()];
+    let start = candidates
+        .into_iter()
+        .flatten()
+        .filter(|d| occurs(event, rule, *d))
+        .map(|d| to_local(d.and_time(event.zoned_start.time()), event.zone))
+        .find(|start| start.date() == day)?;
+    let excluded = event
+        .exdates
+        .iter()
+        .any(|e| *e == start || (event.all_day && e.date() == day));
+    if excluded || rule.until.is_some_and(|until| start > until) {
+        return None;
+    }
+    Some(start)
+}
+
+/// The events taking place on `day` with recurring events expanded, and warnings about the
+/// events that could not be handled.
+pub fn events_on(events: &[Event], day: NaiveDate) -> (Vec<Event>, Vec<String>) {
+    // Changed instances replace the instance of the recurring event they were moved from.
+    let moved: HashSet<(&str, NaiveDateTime)> = events
+        .iter()
+        .filter_map(|e| e.recurrence_id.map(|r| (e.uid.as_str(), r)))
+        .collect();
+    let mut found = Vec::new();
+    let mut warnings = Vec::new();
+    for event in events {
+        let start = match (&event.rrule, event.recurrence_id) {
+            (Some(rule), None) => match parse_rule(rule) {
+                Ok(rule) => occurrence(event, &rule, day)
+                    .filter(|start| !moved.contains(&(event.uid.as_str(), *start))),
+                Err(reason) => {
+                    if event.start.date() <= day {
+                        warnings.push(format!(
+                            "Skipped recurring event '{}': {}.",
+                            event.summary, reason
+                        ));
+                    }
+                    None
+                }
+            },
+            _ => Some(event.start).filter(|s| s.date() == day),
+        };
+        let Some(start) = start else {
+            continue;
+        };
+        if let Some(tz) = &event.unknown_tz {
+            warnings.push(format!(
+                "'{}' uses the unknown time zone {}, its time is taken as local time.",
+                event.summary, tz
+            ));
+        }
+        found.push(Event {
+            start,
+            end: event.end.map(|end| start + (end - event.start)),
+            ..event.clone()
+        });
+    }
+    (found, warnings)
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+pub const NOTE: u32 = 1;
+pub const FOLDER: u32 = 2;
+pub const RESOURCE: u32 = 4;
+pub const TAG: u32 = 5;
+pub const NOTE_TAG: u32 = 6;
+
+/// An item of a JEX archive: a note, notebook, resource, tag or the link between a note and a tag.
+pub struct Item {
+    pub id: String,
+    pub kind: u32,
+    pub title: String,
+    pub body: String,
+    pub props: BTreeMap<String, String>,
+}
+
+impl Item {
+    pub fn prop(&self, key: &str) -> &str {
+        self.props.get(key).map(String::as_str).unwrap_or("")
+    }
+
+    pub fn timestamp(&self, key: &str) -> Option<String> {
+        DateTime::parse_from_rfc3339(self.prop(key)).ok().map(|d| {
+            d.with_timezone(&Local)
+                .format("%Y-%m-%dT%H:%M:%S")
+                .to_string()
+        })
+    }
+}
+
+// Items are stored as the title, a blank line, the body, a blank line and `key: value` properties.
+pub fn parse_item(content: &str) -> Option<Item> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut split = lines.len();
+    while split > 0 && !lines[split - 1].trim().is_empty() {
+        split -= 1;
+    }
+    let mut props = BTreeMap::new();
+    for line in &lines[split..] {
+        let (key, value) = line.split_once(':')?;
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        props.insert(key.to_string(), value.replace("\\n", "\n"));
+    }
+    let head = &lines[..split];
+    let title = head
+        .first()
+        .map(|t| t.trim().to_string())
+        .unwrap_or_default();
+    let body = head
+        .get(1..)
+        .unwrap_or_default()
+        .join("\n")
+        .trim()
+        .to_string();
+    Some(Item {
+        id: props.get("id")?.clone(),
+        kind: props.get("type_")?.parse().ok()?,
+        title,
+        body,
+        props,
+    })
+}
+
+pub struct Archive {
+    pub items: Vec<Item>,
+    // resource id -> file content
+    pub resources: BTreeMap<String, Vec<u8>>,
+}
+
+pub fn read(path: &Path) -> Result<Archive, io::Error> {
+    let mut archive = tar::Archive::new(File::open(path)?);
+    let mut items = Vec::new();
+    let mut resources = BTreeMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        if let Some(file) = name.strip_prefix("resources/") {
+            let id = file.split('.').next().unwrap_or(file).to_string();
+            resources.insert(id, data);
+        } else if name.ends_with(".md") {
+            items.extend(parse_item(&String::from_utf8_lossy(&data)));
+        }
+    }
+    if items.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a Joplin export archive.",
+        ));
+    }
+    Ok(Archive { items, resources })
+}
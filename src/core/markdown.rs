@@ -0,0 +1,435 @@
+use chrono::NaiveTime;
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+pub struct Link {
+    pub target: String,
+    pub wiki: bool,
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '/'
+}
+
+fn strip_inline_code(line: &str) -> String {
+    let mut out = String::new();
+    let mut in_code = false;
+    for c in line.chars() {
+        if c == '`' {
+            in_code = !in_code;
+            continue;
+        }
+        if !in_code {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn prose_lines(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if !in_fence {
+            lines.push(strip_inline_code(line));
+        }
+    }
+    lines
+}
+
+pub fn tags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for line in prose_lines(content) {
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            // `(#tag)` is a tag, the anchor of `[text](#section)` is not.
+            let anchor = i >= 2 && chars[i - 2] == ']' && chars[i - 1] == '(';
+            let boundary =
+                i == 0 || chars[i - 1].is_whitespace() || (chars[i - 1] == '(' && !anchor);
+            if chars[i] == '#' && boundary {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && is_tag_char(chars[end]) {
+                    end += 1;
+                }
+                let tag: String = chars[start..end].iter().collect();
+                let tag = tag.trim_end_matches(['-', '/']).to_lowercase();
+                if !tag.is_empty()
+                    && !tag.chars().all(|c| c.is_ascii_digit())
+                    && !tags.contains(&tag)
+                {
+                    tags.push(tag);
+                }
+                i = end;
+            } else {
+                i += 1;
+            }
+        }
+    }
+    tags
+}
+
+pub fn links(content: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    for line in prose_lines(content) {
+        let mut rest = line.as_str();
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            match after.find("]]") {
+                Some(end) => {
+                    let inner = &after[..end];
+                    let target = inner.split(['|', '#']).next().unwrap_or("").trim();
+                    if !target.is_empty() {
+                        links.push(Link {
+                            target: target.to_string(),
+                            wiki: true,
+                        });
+                    }
+                    rest = &after[end + 2..];
+                }
+                None => break,
+            }
+        }
+
+        let mut rest = line.as_str();
+        while let Some(start) = rest.find("](") {
+            let after = &rest[start + 2..];
+            match after.find(')') {
+                Some(end) => {
+                    let target = after[..end].split('#').next().unwrap_or("").trim();
+                    if !target.is_empty()
+                        && !target.contains("://")
+                        && !target.starts_with("mailto:")
+                    {
+                        links.push(Link {
+                            target: target.to_string(),
+                            wiki: false,
+                        });
+                    }
+                    rest = &after[end + 1..];
+                }
+                None => break,
+            }
+        }
+    }
+    links
+}
+
+fn is_url(target: &str) -> bool {
+    target.contains("://") || target.starts_with("mailto:") || target.starts_with('#')
+}
+
+// The new target of a link, keeping its `#fragment`.
+fn mapped(
+    target: &str,
+    wiki: bool,
+    url: &mut dyn FnMut(&Link) -> Option<String>,
+) -> Option<String> {
+    let (path, fragment) = target.split_once('#').unwrap_or((target, ""));
+    let new = url(&Link {
+        target: path.trim().to_string(),
+        wiki,
+    })?;
+    let new = if fragment.is_empty() {
+        new
+    } else {
+        format!("{}#{}", new, fragment)
+    };
+    Some(if new.contains(char::is_whitespace) {
+        format!("<{}>", new)
+    } else {
+        new
+    })
+}
+
+// A link being read: its source range, its new target and the range of its text.
+struct OpenLink {
+    range: Range<usize>,
+    link_type: LinkType,
+    image: bool,
+    external: bool,
+    new: Option<String>,
+    text: Option<Range<usize>>,
+}
+
+fn with_title(url: &str, title: &str) -> String {
+    if title.is_empty() {
+        url.to_string()
+    } else {
+        format!("{} \"{}\"", url, title.replace('"', "\\\""))
+    }
+}
+
+// Applies the `edits` that lie within `range` to that part of `content`.
+fn splice(content: &str, range: Range<usize>, edits: &mut Vec<(Range<usize>, String)>) -> String {
+    let (mut inner, rest): (Vec<_>, Vec<_>) = edits
+        .drain(..)
+        .partition(|(r, _)| range.start <= r.start && r.end <= range.end);
+    *edits = rest;
+    inner.sort_by_key(|(r, _)| r.start);
+    let mut out = String::new();
+    let mut done = range.start;
+    for (r, replacement) in inner {
+        if r.start < done {
+            continue;
+        }
+        out.push_str(&content[done..r.start]);
+        out.push_str(&replacement);
+        done = r.end;
+    }
+    out.push_str(&content[done..range.end]);
+    out
+}
+
+/// Rewrites the wiki and markdown links of `content`. `url` returns the new target of a link,
+/// links it returns `None` for are replaced by their text, and their reference definitions
+/// and autolinks are removed. Wiki links become markdown links. Images and embeds are passed
+/// to `url` as well and replaced by their alt text when it returns `None`.
+pub fn map_links(content: &str, url: &mut dyn FnMut(&Link) -> Option<String>) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_WIKILINKS;
+    let mut parser = Parser::new_ext(content, options).into_offset_iter();
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    // Images can sit inside links, so both are tracked.
+    let mut open: Vec<OpenLink> = Vec::new();
+    for (event, range) in parser.by_ref() {
+        for link in open.iter_mut() {
+            if !matches!(event, Event::End(TagEnd::Link | TagEnd::Image)) {
+                let start = link.text.as_ref().map(|t| t.start).unwrap_or(range.start);
+                link.text = Some(start..range.end.max(start));
+            }
+        }
+        let image = matches!(event, Event::Start(Tag::Image { .. }));
+        match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                ..
+            })
+            | Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                ..
+            }) => {
+                let wiki = matches!(link_type, LinkType::WikiLink { .. });
+                let new = if is_url(&dest_url) {
+                    None
+                } else {
+                    mapped(&dest_url, wiki, url)
+                };
+                open.push(OpenLink {
+                    range,
+                    link_type,
+                    image,
+                    external: is_url(&dest_url),
+                    new: new.map(|u| with_title(&u, &title)),
+                    text: None,
+                });
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                let Some(link) = open.pop() else {
+                    continue;
+                };
+                let text = match link.text {
+                    Some(text) => splice(content, text, &mut edits),
+                    None => String::new(),
+                };
+                let replacement = match (link.link_type, link.new) {
+                    _ if link.external => continue,
+                    // Their definition is rewritten instead.
+                    (LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut, Some(_)) => {
+                        continue
+                    }
+                    (_, Some(new)) if link.image => format!("![{}]({})", text, new),
+                    (LinkType::Autolink | LinkType::Email, None) => String::new(),
+                    (_, Some(new)) => format!("[{}]({})", text, new),
+                    (_, None) => text,
+                };
+                edits.push((link.range, replacement));
+            }
+            _ => {}
+        }
+    }
+    for (_, definition) in parser.reference_definitions().iter() {
+        if is_url(&definition.dest) {
+            continue;
+        }
+        let mut span = definition.span.clone();
+        let source = &content[span.clone()];
+        let replacement = match mapped(&definition.dest, false, url) {
+            Some(new) => {
+                let label = source
+                    .find("]:")
+                    .map(|e| &source[..e + 2])
+                    .unwrap_or(source);
+                let title = definition.title.as_deref().unwrap_or("");
+                let ending = &source[source.trim_end().len()..];
+                format!(
+                    "{} {}{}",
+                    label.trim_start(),
+                    with_title(&new, title),
+                    ending
+                )
+            }
+            None => {
+                if content[span.end..].starts_with('\n') {
+                    span.end += 1;
+                }
+                String::new()
+            }
+        };
+        edits.push((span, replacement));
+    }
+
+    splice(content, 0..content.len(), &mut edits)
+}
+
+pub struct Heading {
+    pub level: usize,
+    pub text: String,
+    pub line: usize,
+}
+
+pub struct Checkbox {
+    pub line: usize,
+    pub done: bool,
+    pub text: String,
+}
+
+pub fn headings(content: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut in_fence = false;
+    for (i, line) in content.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let level = line.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+            headings.push(Heading {
+                level,
+                text: line[level..].trim().to_string(),
+                line: i + 1,
+            });
+        }
+    }
+    headings
+}
+
+/// Shifts every heading of `content` down by `levels`, capped at level 6.
+pub fn demote_headings(content: &str, levels: usize) -> String {
+    let headings: Vec<usize> = headings(content).iter().map(|h| h.line).collect();
+    let mut out = String::new();
+    for (i, line) in content.lines().enumerate() {
+        if headings.contains(&(i + 1)) {
+            let level = line.chars().take_while(|c| *c == '#').count();
+            out.push_str(&"#".repeat((level + levels).min(6)));
+            out.push_str(&line[level..]);
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn checkbox(line: &str) -> Option<(bool, &str)> {
+    let item = line.trim_start();
+    let item = item
+        .strip_prefix("- ")
+        .or_else(|| item.strip_prefix("* "))
+        .or_else(|| item.strip_prefix("+ "))?;
+    let (done, text) = if let Some(text) = item.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = item
+        .strip_prefix("[x]")
+        .or_else(|| item.strip_prefix("[X]"))
+    {
+        (true, text)
+    } else {
+        return None;
+    };
+    Some((done, text.trim()))
+}
+
+pub fn checkboxes(content: &str) -> Vec<Checkbox> {
+    let mut items = Vec::new();
+    let mut in_fence = false;
+    for (i, line) in content.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some((done, text)) = checkbox(line) {
+            items.push(Checkbox {
+                line: i + 1,
+                done,
+                text: text.to_string(),
+            });
+        }
+    }
+    items
+}
+
+pub struct TimedEntry {
+    pub line: usize,
+    pub start: NaiveTime,
+    pub end: Option<NaiveTime>,
+    pub text: String,
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M").ok()
+}
+
+pub fn timed_entries(content: &str) -> Vec<TimedEntry> {
+    let mut entries = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let item = line.trim_start();
+        let Some(item) = item.strip_prefix("- ").or_else(|| item.strip_prefix("* ")) else {
+            continue;
+        };
+        let Some((time, text)) = item.trim_start().split_once(' ') else {
+            continue;
+        };
+        let (start, end) = match time.split_once('-') {
+            Some((start, end)) => (parse_time(start), parse_time(end)),
+            None => (parse_time(time), None),
+        };
+        if let Some(start) = start {
+            entries.push(TimedEntry {
+                line: i + 1,
+                start,
+                end,
+                text: text.trim().to_string(),
+            });
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_skip_anchor_links() {
+        let content = "See [below](#setup) (#travel) and #work.\n";
+        assert_eq!(tags(content), vec!["travel", "work"]);
+    }
+}
@@ -0,0 +1,260 @@
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    pub fn from_config(value: &str) -> Option<Format> {
+        match value {
+            "yaml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+
+    fn fence(&self) -> &'static str {
+        match self {
+            Format::Yaml => "---",
+            Format::Toml => "+++",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct NoteMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_date",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_date",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub modified: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_list",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_list",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub aliases: Vec<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+// TOML datetimes arrive as a single-entry table, YAML and quoted TOML dates as strings.
+fn deserialize_date<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        Some(serde_json::Value::String(s)) => Some(s),
+        Some(serde_json::Value::Object(map)) => map
+            .values()
+            .next()
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        _ => None,
+    })
+}
+
+fn deserialize_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        Some(serde_json::Value::String(s)) => s
+            .split([',', ' '])
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect(),
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(String::from)
+            .collect(),
+        _ => Vec::new(),
+    })
+}
+
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, TIMESTAMP_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+}
+
+pub fn now() -> String {
+    Local::now().format(TIMESTAMP_FORMAT).to_string()
+}
+
+impl NoteMeta {
+    pub fn new(title: &str) -> NoteMeta {
+        NoteMeta {
+            title: Some(title.to_string()),
+            created: Some(now()),
+            ..Default::default()
+        }
+    }
+
+    pub fn created(&self) -> Option<NaiveDateTime> {
+        self.created.as_deref().and_then(parse_timestamp)
+    }
+
+    pub fn modified(&self) -> Option<NaiveDateTime> {
+        self.modified.as_deref().and_then(parse_timestamp)
+    }
+
+    pub fn render(&self, format: Format) -> Result<String, io::Error> {
+        let data = match format {
+            Format::Yaml => serde_yaml::to_string(self)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?,
+            Format::Toml => toml::to_string(self)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?,
+        };
+        Ok(format!(
+            "{}\n{}\n{}\n",
+            format.fence(),
+            data.trim_end(),
+            format.fence()
+        ))
+    }
+}
+
+fn split_fence(content: &str, format: Format) -> Option<(&str, &str)> {
+    let fence = format.fence();
+    let rest = content.strip_prefix(fence)?;
+    let rest = rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == fence {
+            let body = &rest[offset + line.len()..];
+            return Some((&rest[..offset], body));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+pub fn split(content: &str) -> Option<(Format, &str, &str)> {
+    [Format::Yaml, Format::Toml]
+        .into_iter()
+        .find_map(|format| split_fence(content, format).map(|(data, body)| (format, data, body)))
+}
+
+pub fn body(content: &str) -> &str {
+    split(content).map(|(_, _, body)| body).unwrap_or(content)
+}
+
+pub fn parse(content: &str) -> Option<(Format, NoteMeta)> {
+    let (format, data, _) = split(content)?;
+    if format == Format::Yaml && data.trim().is_empty() {
+        return Some((format, NoteMeta::default()));
+    }
+    let (mut meta, raw): (NoteMeta, BTreeMap<String, serde_json::Value>) = match format {
+        Format::Yaml => (
+            serde_yaml::from_str(data).ok()?,
+            serde_yaml::from_str(data).ok()?,
+        ),
+        Format::Toml => (toml::from_str(data).ok()?, toml::from_str(data).ok()?),
+    };
+    // Timestamps that are not dates are kept as they are instead of being dropped.
+    for (key, parsed) in [("created", &meta.created), ("modified", &meta.modified)] {
+        if let (None, Some(value)) = (parsed, raw.get(key)) {
+            meta.extra.insert(key.to_string(), value.clone());
+        }
+    }
+    Some((format, meta))
+}
+
+pub fn write(content: &str, meta: &NoteMeta, format: Format) -> Result<String, io::Error> {
+    Ok(format!("{}{}", meta.render(format)?, body(content)))
+}
+
+// Sets the top-level `modified` key, keeping its quoting, or adds it before the first TOML table.
+fn set_modified(data: &str, format: Format, value: &str) -> String {
+    let separator = match format {
+        Format::Yaml => ':',
+        Format::Toml => '=',
+    };
+    let mut lines: Vec<String> = Vec::new();
+    let mut found = false;
+    for line in data.lines() {
+        match line.split_once(separator) {
+            Some((key, old))
+                if !found && !line.starts_with(char::is_whitespace) && key.trim() == "modified" =>
+            {
+                let new = match old.trim().chars().next() {
+                    Some(quote @ ('"' | '\'')) => format!("{quote}{value}{quote}"),
+                    _ => value.to_string(),
+                };
+                lines.push(format!("{}{} {}", key, separator, new));
+                found = true;
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    if !found {
+        let (line, at) = match format {
+            Format::Yaml => (format!("modified: {value}"), lines.len()),
+            Format::Toml => (
+                format!("modified = \"{value}\""),
+                lines
+                    .iter()
+                    .position(|l| l.trim_start().starts_with('['))
+                    .unwrap_or(lines.len()),
+            ),
+        };
+        lines.insert(at, line);
+    }
+    lines.iter().map(|l| format!("{l}\n")).collect()
+}
+
+/// Bumps `modified`, leaving the rest of the front matter untouched.
+pub fn touch(content: &str) -> Result<Option<String>, io::Error> {
+    if parse(content).is_none() {
+        return Ok(None);
+    }
+    let Some((format, data, _)) = split(content) else {
+        return Ok(None);
+    };
+    let start = data.as_ptr() as usize - content.as_ptr() as usize;
+    Ok(Some(format!(
+        "{}{}{}",
+        &content[..start],
+        set_modified(data, format, &now()),
+        &content[start + data.len()..]
+    )))
+}
+
+pub fn initial(title: &str, front_matter: &str) -> Result<String, io::Error> {
+    match Format::from_config(front_matter) {
+        Some(format) => NoteMeta::new(title).render(format),
+        None => Ok(String::new()),
+    }
+}
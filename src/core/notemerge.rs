@@ -0,0 +1,228 @@
+use crate::core::meta::{self, Format};
+
+// `- item`, `* item`, `+ item`, `1. item` and `1) item`, including tasks.
+fn is_list_item(line: &str) -> bool {
+    let line = line.trim_start();
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    ["- ", "* ", "+ "].iter().any(|m| line.starts_with(m))
+        || (digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") ")))
+}
+
+// List items, their indented continuation lines and the blank lines between them.
+fn is_list_line(line: &str) -> bool {
+    line.trim().is_empty() || line.starts_with(char::is_whitespace) || is_list_item(line)
+}
+
+// The lines a side inserted before each base line and after the last one. None when the side
+// changed or removed a base line.
+fn insertions<'a>(base: &[&str], side: &'a str) -> Option<Vec<Vec<&'a str>>> {
+    let mut gaps = vec![Vec::new(); base.len() + 1];
+    let mut next = 0;
+    for line in side.lines() {
+        if next < base.len() && line == base[next] {
+            next += 1;
+        } else {
+            gaps[next].push(line);
+        }
+    }
+    (next == base.len()).then_some(gaps)
+}
+
+// Both sides inserted at the same place. That is only safe at the end of a note or when all
+// inserted lines are list items; ours come first, then the items theirs added.
+fn combine<'a>(ours: &[&'a str], theirs: &[&'a str], at_end: bool) -> Option<Vec<&'a str>> {
+    if theirs.is_empty() || ours == theirs {
+        return Some(ours.to_vec());
+    }
+    if ours.is_empty() {
+        return Some(theirs.to_vec());
+    }
+    if !at_end && !ours.iter().chain(theirs).all(|l| is_list_line(l)) {
+        return None;
+    }
+    let shared = ours.iter().zip(theirs).take_while(|(o, t)| o == t).count();
+    let mut lines = ours.to_vec();
+    lines.extend(
+        theirs[shared..]
+            .iter()
+            .filter(|l| !(is_list_item(l) && ours.contains(l))),
+    );
+    Some(lines)
+}
+
+fn union(base: &str, ours: &str, theirs: &str) -> Option<String> {
+    if ours == theirs || theirs == base {
+        return Some(ours.to_string());
+    }
+    if ours == base {
+        return Some(theirs.to_string());
+    }
+    let base_lines: Vec<&str> = base.lines().collect();
+    let ours_gaps = insertions(&base_lines, ours)?;
+    let theirs_gaps = insertions(&base_lines, theirs)?;
+    let mut merged = Vec::new();
+    for (i, (o, t)) in ours_gaps.iter().zip(&theirs_gaps).enumerate() {
+        merged.extend(combine(o, t, i == base_lines.len())?);
+        merged.extend(base_lines.get(i));
+    }
+    let mut result = merged.join("\n");
+    if ours.ends_with('\n') || theirs.ends_with('\n') {
+        result.push('\n');
+    }
+    Some(result)
+}
+
+// grom bumps `modified` whenever a note is edited, so the timestamps are left out when
+// comparing front matter and merged as the earliest `created` and latest `modified`.
+fn without_timestamps(data: &str) -> Vec<&str> {
+    data.lines()
+        .filter(|line| {
+            let key = line.split([':', '=']).next().unwrap_or("").trim_end();
+            line.starts_with(char::is_whitespace) || (key != "created" && key != "modified")
+        })
+        .collect()
+}
+
+fn front_matter(
+    format: Format,
+    base: &str,
+    (ours, ours_data): (&str, &str),
+    (theirs, theirs_data): (&str, &str),
+) -> Option<String> {
+    let (b, o, t) = (
+        without_timestamps(base),
+        without_timestamps(ours_data),
+        without_timestamps(theirs_data),
+    );
+    let chosen = if o == t || t == b {
+        ours
+    } else if o == b {
+        theirs
+    } else {
+        return None;
+    };
+    let (_, ours_meta) = meta::parse(ours)?;
+    let (_, theirs_meta) = meta::parse(theirs)?;
+    let (_, mut meta) = meta::parse(chosen)?;
+    let created = [ours_meta.created, theirs_meta.created]
+        .into_iter()
+        .flatten()
+        .min();
+    let modified = [ours_meta.modified, theirs_meta.modified]
+        .into_iter()
+        .flatten()
+        .max();
+    if meta.created == created && meta.modified == modified {
+        let (_, _, body) = meta::split(chosen)?;
+        return Some(chosen[..chosen.len() - body.len()].to_string());
+    }
+    meta.created = created;
+    meta.modified = modified;
+    meta.render(format).ok()
+}
+
+/// Merges a note changed on both sides when the changes do not collide: both sides only
+/// appended, or added list items. `base` is None when both sides created the note.
+/// Returns None for real edit collisions.
+pub fn merge(base: Option<&str>, ours: &str, theirs: &str) -> Option<String> {
+    let base = base.unwrap_or("");
+    match (meta::split(ours), meta::split(theirs)) {
+        (Some((format, ours_data, ours_body)), Some((theirs_format, theirs_data, theirs_body)))
+            if format == theirs_format =>
+        {
+            let (base_data, base_body) = match meta::split(base) {
+                Some((base_format, data, body)) if base_format == format => (data, body),
+                _ => ("", base),
+            };
+            let front = front_matter(format, base_data, (ours, ours_data), (theirs, theirs_data))?;
+            Some(format!(
+                "{}{}",
+                front,
+                union(base_body, ours_body, theirs_body)?
+            ))
+        }
+        _ => union(base, ours, theirs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unions_appends_on_both_sides() {
+        let base = "# Day\n\nmorning\n";
+        let ours = "# Day\n\nmorning\nlunch\n";
+        let theirs = "# Day\n\nmorning\nevening\n";
+        assert_eq!(
+            merge(Some(base), ours, theirs).as_deref(),
+            Some("# Day\n\nmorning\nlunch\nevening\n")
+        );
+    }
+
+    #[test]
+    fn unions_list_items_in_the_middle() {
+        let base = "# Todo\n\n- [ ] one\n\n## Notes\n";
+        let ours = "# Todo\n\n- [ ] one\n- [ ] two\n\n## Notes\n";
+        let theirs = "# Todo\n\n- [ ] one\n- [ ] three\n\n## Notes\n";
+        assert_eq!(
+            merge(Some(base), ours, theirs).as_deref(),
+            Some("# Todo\n\n- [ ] one\n- [ ] two\n- [ ] three\n\n## Notes\n")
+        );
+    }
+
+    #[test]
+    fn keeps_a_line_both_sides_added_once() {
+        let base = "- one\n";
+        let ours = "- one\n- two\n";
+        let theirs = "- one\n- two\n- three\n";
+        assert_eq!(
+            merge(Some(base), ours, theirs).as_deref(),
+            Some("- one\n- two\n- three\n")
+        );
+        let theirs = "- one\n- three\n- two\n";
+        assert_eq!(
+            merge(Some(base), ours, theirs).as_deref(),
+            Some("- one\n- two\n- three\n")
+        );
+    }
+
+    #[test]
+    fn refuses_real_conflicts() {
+        let base = "# Day\n\nmorning\n";
+        assert_eq!(
+            merge(Some(base), "# Day\n\nearly\n", "# Day\n\nlate\n"),
+            None
+        );
+        // Prose inserted at the same place on both sides has no safe order.
+        let base = "# Day\n\nmorning\n\nevening\n";
+        let ours = "# Day\n\nmorning\nlunch\n\nevening\n";
+        let theirs = "# Day\n\nmorning\ntea\n\nevening\n";
+        assert_eq!(merge(Some(base), ours, theirs), None);
+    }
+
+    #[test]
+    fn merges_notes_created_on_both_sides() {
+        assert_eq!(
+            merge(None, "- one\n", "- two\n").as_deref(),
+            Some("- one\n- two\n")
+        );
+    }
+
+    #[test]
+    fn merges_front_matter_timestamps() {
+        let base = "---\ntitle: Day\ncreated: 2024-03-05T08:00:00\nmodified: 2024-03-05T08:00:00\n---\n- one\n";
+        let ours = "---\ntitle: Day\ncreated: 2024-03-05T08:00:00\nmodified: 2024-03-05T09:00:00\n---\n- one\n- two\n";
+        let theirs = "---\ntitle: Day\ncreated: 2024-03-05T08:00:00\nmodified: 2024-03-05T10:00:00\n---\n- one\n- three\n";
+        let merged = merge(Some(base), ours, theirs).unwrap();
+        let (_, note_meta) = meta::parse(&merged).unwrap();
+        assert_eq!(note_meta.title.as_deref(), Some("Day"));
+        assert_eq!(note_meta.modified.as_deref(), Some("2024-03-05T10:00:00"));
+        assert!(merged.ends_with("---\n- one\n- two\n- three\n"));
+
+        // Other front matter keys changed on both sides are a conflict.
+        let ours = ours.replace("title: Day", "title: Monday");
+        let theirs = theirs.replace("title: Day", "title: Tuesday");
+        assert_eq!(merge(Some(base), &ours, &theirs), None);
+    }
+}
@@ -0,0 +1,374 @@
+use crate::core::markdown;
+use crate::core::meta::{self, NoteMeta};
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum NoteKind {
+    Diary,
+    Project,
+    Quick,
+    Other,
+}
+
+impl NoteKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteKind::Diary => "diary",
+            NoteKind::Project => "project",
+            NoteKind::Quick => "quick",
+            NoteKind::Other => "other",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DiaryPeriod {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl DiaryPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiaryPeriod::Daily => "daily",
+            DiaryPeriod::Weekly => "weekly",
+            DiaryPeriod::Monthly => "monthly",
+        }
+    }
+}
+
+pub struct Note {
+    pub path: PathBuf,
+    pub rel_path: String,
+    pub kind: NoteKind,
+    pub project: Option<String>,
+    pub period: Option<DiaryPeriod>,
+    pub date: Option<NaiveDate>,
+    pub meta: NoteMeta,
+    pub content: String,
+}
+
+impl Note {
+    pub fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    pub fn title(&self) -> String {
+        if let Some(title) = &self.meta.title {
+            return title.clone();
+        }
+        match (&self.project, self.is_project_start()) {
+            (Some(project), true) => project.clone(),
+            _ => self.name(),
+        }
+    }
+
+    pub fn is_project_start(&self) -> bool {
+        self.kind == NoteKind::Project
+            && (self.rel_path.ends_with("/start.md") || self.rel_path.ends_with("/.start.md"))
+    }
+
+    pub fn body(&self) -> &str {
+        meta::body(&self.content)
+    }
+
+    pub fn body_offset(&self) -> usize {
+        self.content[..self.content.len() - self.body().len()]
+            .lines()
+            .count()
+    }
+
+    pub fn headings(&self) -> Vec<markdown::Heading> {
+        let offset = self.body_offset();
+        let mut headings = markdown::headings(self.body());
+        for h in headings.iter_mut() {
+            h.line += offset;
+        }
+        headings
+    }
+
+    pub fn checkboxes(&self) -> Vec<markdown::Checkbox> {
+        let offset = self.body_offset();
+        let mut items = markdown::checkboxes(self.body());
+        for item in items.iter_mut() {
+            item.line += offset;
+        }
+        items
+    }
+
+    pub fn timed_entries(&self) -> Vec<markdown::TimedEntry> {
+        let offset = self.body_offset();
+        let mut entries = markdown::timed_entries(self.body());
+        for entry in entries.iter_mut() {
+            entry.line += offset;
+        }
+        entries
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self.meta.tags.iter().map(|t| t.to_lowercase()).collect();
+        for tag in markdown::tags(self.body()) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    pub fn links(&self) -> Vec<markdown::Link> {
+        markdown::links(self.body())
+    }
+
+    pub fn created(&self) -> Option<NaiveDateTime> {
+        self.meta
+            .created()
+            .or_else(|| self.date.and_then(|d| d.and_hms_opt(0, 0, 0)))
+    }
+
+    pub fn modified(&self) -> Option<NaiveDateTime> {
+        self.meta.modified().or_else(|| self.created())
+    }
+}
+
+pub struct Notebook {
+    pub root: PathBuf,
+    pub notes: Vec<Note>,
+}
+
+impl Notebook {
+    pub fn load(note_dir: &str) -> Result<Notebook, io::Error> {
+        let root = PathBuf::from(note_dir);
+        let mut files = Vec::new();
+        collect_markdown(&root, &mut files)?;
+        files.sort();
+
+        let mut notes = Vec::new();
+        for path in files {
+            let content = fs::read_to_string(&path)?;
+            notes.push(classify(&root, path, content));
+        }
+        Ok(Notebook { root, notes })
+    }
+
+    pub fn find(&self, rel_path: &str) -> Option<usize> {
+        self.notes.iter().position(|n| n.rel_path == rel_path)
+    }
+
+    pub fn project_start(&self, project: &str) -> Option<usize> {
+        self.find(&format!("projects/{}/start.md", project))
+            .or_else(|| self.find(&format!("projects/{}/.start.md", project)))
+    }
+
+    /// Diary entries overlapping `start..=end` in chronological order, monthly and weekly
+    /// entries before the days they summarize.
+    pub fn diary(&self, start: NaiveDate, end: NaiveDate) -> Vec<usize> {
+        let mut entries = Vec::new();
+        for (i, note) in self.notes.iter().enumerate() {
+            let (Some(period), Some(date)) = (note.period, note.date) else {
+                continue;
+            };
+            let (first, last, order) = match period {
+                DiaryPeriod::Monthly => match month_bounds(date.year(), date.month()) {
+                    Some((first, last)) => (first, last, 0),
+                    None => continue,
+                },
+                DiaryPeriod::Weekly => (date, date + Days::new(6), 1),
+                DiaryPeriod::Daily => (date, date, 2),
+            };
+            if first <= end && last >= start {
+                entries.push((first, order, i));
+            }
+        }
+        entries.sort();
+        entries.into_iter().map(|(_, _, i)| i).collect()
+    }
+
+    pub fn resolve(&self, from: usize, link: &markdown::Link) -> Option<usize> {
+        if link.wiki {
+            let target = link.target.trim_end_matches(".md").to_lowercase();
+            self.notes
+                .iter()
+                .position(|n| n.rel_path.trim_end_matches(".md").to_lowercase() == target)
+                .or_else(|| {
+                    self.notes
+                        .iter()
+                        .position(|n| n.name().to_lowercase() == target)
+                })
+                .or_else(|| {
+                    self.notes.iter().position(|n| {
+                        n.meta.aliases.iter().any(|a| a.to_lowercase() == target)
+                            || n.meta
+                                .title
+                                .as_ref()
+                                .is_some_and(|t| t.to_lowercase() == target)
+                    })
+                })
+                .or_else(|| self.project_start(&link.target))
+        } else {
+            if !link.target.ends_with(".md") {
+                return None;
+            }
+            let base = self.notes[from].path.parent()?;
+            let joined = normalize(&base.join(&link.target));
+            self.notes.iter().position(|n| n.path == joined)
+        }
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                out.pop();
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn collect_markdown(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), io::Error> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+        let path = entry.path();
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.') && n != ".start.md");
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_markdown(&path, files)?;
+        } else if path.extension().is_some_and(|e| e == "md") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn classify(root: &Path, path: PathBuf, content: String) -> Note {
+    let rel_path = path
+        .strip_prefix(root)
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let parts: Vec<&str> = rel_path.split('/').collect();
+
+    let mut kind = NoteKind::Other;
+    let mut project = None;
+    let mut period = None;
+    let mut date = None;
+    match parts[0] {
+        "diary" => {
+            kind = NoteKind::Diary;
+            (period, date) = parse_diary_path(&parts[1..]);
+        }
+        "projects" if parts.len() > 2 => {
+            kind = NoteKind::Project;
+            project = Some(parts[1].to_string());
+        }
+        "quick-notes" => kind = NoteKind::Quick,
+        _ => {}
+    }
+
+    Note {
+        path,
+        rel_path,
+        kind,
+        project,
+        period,
+        date,
+        meta: meta::parse(&content).map(|(_, m)| m).unwrap_or_default(),
+        content,
+    }
+}
+
+fn parse_month(name: &str) -> Option<u32> {
+    NaiveDate::parse_from_str(&format!("1 {} 2000", name), "%d %B %Y")
+        .ok()
+        .map(|d| d.month())
+}
+
+fn parse_diary_path(parts: &[&str]) -> (Option<DiaryPeriod>, Option<NaiveDate>) {
+    match parts {
+        [year, month, "month.md"] => {
+            let date = year
+                .parse()
+                .ok()
+                .zip(parse_month(month))
+                .and_then(|(y, m)| NaiveDate::from_ymd_opt(y, m, 1));
+            (Some(DiaryPeriod::Monthly), date)
+        }
+        [year, _, week, "week.md"] => {
+            let date = year
+                .parse()
+                .ok()
+                .zip(week.trim_start_matches("week").parse().ok())
+                .and_then(|(y, w)| NaiveDate::from_isoywd_opt(y, w, chrono::Weekday::Mon));
+            (Some(DiaryPeriod::Weekly), date)
+        }
+        [_, _, _, day] => {
+            let date = NaiveDate::parse_from_str(day.trim_end_matches(".md"), "%m-%d-%Y").ok();
+            (Some(DiaryPeriod::Daily), date)
+        }
+        _ => (None, None),
+    }
+}
+
+pub fn daily_diary_path(note_dir: &str, date: NaiveDate) -> String {
+    format!(
+        "{}/diary/{}/{}/week{}/{}.md",
+        note_dir,
+        date.year(),
+        date.format("%B"),
+        date.iso_week().week(),
+        date.format("%m-%d-%Y")
+    )
+}
+
+pub fn weekly_diary_path(note_dir: &str, date: NaiveDate) -> String {
+    format!(
+        "{}/diary/{}/{}/week{}/week.md",
+        note_dir,
+        date.year(),
+        date.format("%B"),
+        date.iso_week().week(),
+    )
+}
+
+pub fn monthly_diary_path(note_dir: &str, date: NaiveDate) -> String {
+    format!(
+        "{}/diary/{}/{}/month.md",
+        note_dir,
+        date.year(),
+        date.format("%B"),
+    )
+}
+
+pub fn diary_title(period: DiaryPeriod, date: NaiveDate) -> String {
+    match period {
+        DiaryPeriod::Daily => date.format("%A, %B %d %Y").to_string(),
+        DiaryPeriod::Weekly => format!("Week {}, {}", date.iso_week().week(), date.year()),
+        DiaryPeriod::Monthly => date.format("%B %Y").to_string(),
+    }
+}
+
+pub fn month_bounds(year: i32, month: u32) -> Option<(NaiveDate, NaiveDate)> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let end = start.checked_add_months(Months::new(1))?.pred_opt()?;
+    Some((start, end))
+}
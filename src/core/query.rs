@@ -0,0 +1,403 @@
+use crate::core::notes::{Note, Notebook};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use std::cmp::Ordering;
+use std::io;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Match,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+struct Term {
+    field: String,
+    op: Op,
+    value: String,
+}
+
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+pub struct Query {
+    filter: Option<Expr>,
+    sort: Vec<(String, bool)>,
+    limit: Option<usize>,
+}
+
+const FIELDS: [&str; 11] = [
+    "tag", "project", "kind", "path", "title", "status", "alias", "text", "created", "modified",
+    "date",
+];
+
+const SORT_FIELDS: [&str; 8] = [
+    "path", "title", "project", "kind", "status", "created", "modified", "date",
+];
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn tokenize(input: &str) -> Result<Vec<String>, io::Error> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' | ')' if !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(c.to_string());
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if quoted {
+        return Err(invalid("Unterminated quote in query.".to_string()));
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn parse_term(token: &str) -> Result<Term, io::Error> {
+    let ops = [
+        (">=", Op::Ge),
+        ("<=", Op::Le),
+        ("!=", Op::Ne),
+        (">", Op::Gt),
+        ("<", Op::Lt),
+        ("=", Op::Eq),
+        (":", Op::Match),
+    ];
+    let split = ops
+        .iter()
+        .filter_map(|(symbol, op)| token.find(symbol).map(|i| (i, *symbol, *op)))
+        .min_by_key(|(i, symbol, _)| (*i, std::cmp::Reverse(symbol.len())));
+    match split {
+        Some((i, symbol, op)) => {
+            let field = token[..i].to_lowercase();
+            if !FIELDS.contains(&field.as_str()) {
+                return Err(invalid(format!("Unknown query field '{}'.", field)));
+            }
+            let value = token[i + symbol.len()..].to_string();
+            if matches!(field.as_str(), "created" | "modified" | "date")
+                && parse_date(&value).is_none()
+            {
+                return Err(invalid(format!("Invalid date '{}' for {}.", value, field)));
+            }
+            Ok(Term { field, op, value })
+        }
+        None => Ok(Term {
+            field: "text".to_string(),
+            op: Op::Match,
+            value: token.to_string(),
+        }),
+    }
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|t| t.as_str())
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, io::Error> {
+        let mut left = self.and()?;
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("or")) {
+            self.next();
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, io::Error> {
+        let mut left = self.unary()?;
+        while let Some(token) = self.peek() {
+            if token.eq_ignore_ascii_case("or") || token == ")" {
+                break;
+            }
+            if token.eq_ignore_ascii_case("and") {
+                self.next();
+            }
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, io::Error> {
+        match self.next() {
+            Some(t) if t.eq_ignore_ascii_case("not") => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(t) if t == "(" => {
+                let expr = self.or()?;
+                match self.next().as_deref() {
+                    Some(")") => Ok(expr),
+                    _ => Err(invalid("Missing closing parenthesis.".to_string())),
+                }
+            }
+            Some(t) if t == ")" => Err(invalid("Unexpected ')'.".to_string())),
+            Some(t) => parse_term(&t).map(Expr::Term),
+            None => Err(invalid("Unexpected end of query.".to_string())),
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Result<Query, io::Error> {
+    let mut sort = Vec::new();
+    let mut limit = None;
+    let mut tokens = Vec::new();
+    for token in tokenize(input)? {
+        if let Some(key) = token.strip_prefix("sort:") {
+            let (field, descending) = match key.strip_prefix('-') {
+                Some(field) => (field, true),
+                None => (key, false),
+            };
+            if !SORT_FIELDS.contains(&field) {
+                return Err(invalid(format!("Unknown sort field '{}'.", field)));
+            }
+            sort.push((field.to_string(), descending));
+        } else if let Some(value) = token.strip_prefix("limit:") {
+            limit = Some(
+                value
+                    .parse()
+                    .map_err(|_| invalid(format!("Invalid limit '{}'.", value)))?,
+            );
+        } else {
+            tokens.push(token);
+        }
+    }
+
+    let filter = if tokens.is_empty() {
+        None
+    } else {
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if parser.peek().is_some() {
+            return Err(invalid("Unexpected ')'.".to_string()));
+        }
+        Some(expr)
+    };
+    Ok(Query {
+        filter,
+        sort,
+        limit,
+    })
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    match value {
+        "today" => Some(Local::now().date_naive()),
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d"))
+            .ok(),
+    }
+}
+
+fn date_field(note: &Note, field: &str) -> Option<NaiveDateTime> {
+    match field {
+        "created" => note.created(),
+        "modified" => note.modified(),
+        "date" => note.date.and_then(|d| d.and_hms_opt(0, 0, 0)),
+        _ => None,
+    }
+}
+
+fn compare_date(note: &Note, term: &Term) -> bool {
+    let (Some(actual), Some(expected)) = (date_field(note, &term.field), parse_date(&term.value))
+    else {
+        return false;
+    };
+    let actual = actual.date();
+    match term.op {
+        Op::Match | Op::Eq => actual == expected,
+        Op::Ne => actual != expected,
+        Op::Lt => actual < expected,
+        Op::Le => actual <= expected,
+        Op::Gt => actual > expected,
+        Op::Ge => actual >= expected,
+    }
+}
+
+fn compare_text(value: &str, term: &Term) -> bool {
+    let value = value.to_lowercase();
+    let expected = term.value.to_lowercase();
+    match term.op {
+        Op::Match => value.contains(&expected),
+        Op::Ne => value != expected,
+        _ => value == expected,
+    }
+}
+
+fn matches_term(note: &Note, term: &Term) -> bool {
+    let exact = |values: &[String]| {
+        let expected = term.value.to_lowercase();
+        let found = values.iter().any(|v| v.to_lowercase() == expected);
+        if term.op == Op::Ne {
+            !found
+        } else {
+            found
+        }
+    };
+    match term.field.as_str() {
+        "tag" => {
+            let prefix = format!("{}/", term.value.to_lowercase());
+            let nested = note.tags().iter().any(|t| t.starts_with(&prefix));
+            if term.op == Op::Ne {
+                !nested && exact(&note.tags())
+            } else {
+                nested || exact(&note.tags())
+            }
+        }
+        "project" => exact(&note.project.clone().into_iter().collect::<Vec<_>>()),
+        "kind" => exact(&[note.kind.as_str().to_string()]),
+        "status" => exact(&note.meta.status.clone().into_iter().collect::<Vec<_>>()),
+        "alias" => exact(&note.meta.aliases),
+        "path" => compare_text(&note.rel_path, term),
+        "title" => compare_text(&note.title(), term),
+        "text" => compare_text(note.body(), term),
+        _ => compare_date(note, term),
+    }
+}
+
+fn matches(note: &Note, expr: &Expr) -> bool {
+    match expr {
+        Expr::And(a, b) => matches(note, a) && matches(note, b),
+        Expr::Or(a, b) => matches(note, a) || matches(note, b),
+        Expr::Not(e) => !matches(note, e),
+        Expr::Term(term) => matches_term(note, term),
+    }
+}
+
+fn sort_key(note: &Note, field: &str) -> String {
+    match field {
+        "created" | "modified" | "date" => date_field(note, field)
+            .map(|d| d.format("%Y-%m-%dT%H:%M:%S").to_string())
+            .unwrap_or_default(),
+        "title" => note.title().to_lowercase(),
+        "project" => note.project.clone().unwrap_or_default(),
+        "kind" => note.kind.as_str().to_string(),
+        "status" => note.meta.status.clone().unwrap_or_default(),
+        _ => note.rel_path.clone(),
+    }
+}
+
+impl Query {
+    pub fn run<'a>(&self, notebook: &'a Notebook) -> Vec<&'a Note> {
+        let mut results: Vec<&Note> = notebook
+            .notes
+            .iter()
+            .filter(|n| self.filter.as_ref().is_none_or(|f| matches(n, f)))
+            .collect();
+        results.sort_by(|a, b| {
+            for (field, descending) in &self.sort {
+                let ordering = sort_key(a, field).cmp(&sort_key(b, field));
+                if ordering != Ordering::Equal {
+                    return if *descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    };
+                }
+            }
+            a.rel_path.cmp(&b.rel_path)
+        });
+        if let Some(limit) = self.limit {
+            results.truncate(limit);
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::TempNotebook;
+
+    fn paths(query: &str, notebook: &Notebook) -> Vec<String> {
+        parse(query)
+            .unwrap()
+            .run(notebook)
+            .iter()
+            .map(|n| n.rel_path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        for query in [
+            "created>=2024-13-01",
+            "date:yesterday",
+            "sort:tag",
+            "colour:red",
+            "(tag:a",
+            "tag:a)",
+            "title:\"open",
+            "limit:many",
+        ] {
+            assert!(parse(query).is_err(), "{query} should be rejected");
+        }
+        assert!(parse("created>=2024-03 modified<=today sort:-date").is_ok());
+    }
+
+    #[test]
+    fn combines_terms() {
+        let temp = TempNotebook::new(&[
+            (
+                "projects/acme/start.md",
+                "---\ntitle: Acme\ncreated: 2024-03-10T09:00:00\ntags: [client]\n---\nplan\n",
+            ),
+            (
+                "projects/acme/meeting.md",
+                "---\ncreated: 2024-01-05T09:00:00\n---\n#meeting/weekly notes\n",
+            ),
+            ("quick-notes/idea.md", "an idea #meeting\n"),
+        ]);
+        let notebook = temp.load();
+        assert_eq!(
+            paths("tag:meeting and project:acme", &notebook),
+            ["projects/acme/meeting.md"]
+        );
+        assert_eq!(
+            paths("(kind:quick or tag:client) sort:path", &notebook),
+            ["projects/acme/start.md", "quick-notes/idea.md"]
+        );
+        assert_eq!(
+            paths("created>=2024-02 and not kind:quick", &notebook),
+            ["projects/acme/start.md"]
+        );
+        assert_eq!(
+            paths("project:acme sort:-created limit:1", &notebook),
+            ["projects/acme/start.md"]
+        );
+        assert_eq!(
+            paths("title:\"acme\" idea", &notebook),
+            Vec::<String>::new()
+        );
+    }
+}
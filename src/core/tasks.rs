@@ -0,0 +1,332 @@
+use crate::core::meta;
+use crate::core::notes::Notebook;
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+pub struct Task {
+    pub id: String,
+    pub path: PathBuf,
+    pub rel_path: String,
+    pub line: usize,
+    pub done: bool,
+    pub text: String,
+    pub description: String,
+    pub priority: Option<char>,
+    pub due: Option<NaiveDate>,
+    pub project: Option<String>,
+    pub people: Vec<String>,
+    pub projects: Vec<String>,
+    pub tags: Vec<String>,
+    pub annotations: BTreeMap<String, String>,
+}
+
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+pub fn parse_relative_date(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let value = value.to_lowercase();
+    match value.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.checked_add_days(Days::new(1)),
+        _ => {}
+    }
+    if let Some(offset) = value.strip_prefix('+') {
+        let (last, _) = offset.char_indices().last()?;
+        let (count, unit) = offset.split_at(last);
+        let count: u64 = count.parse().ok()?;
+        let days = match unit {
+            "d" => count,
+            "w" => count.checked_mul(7)?,
+            _ => return None,
+        };
+        return today.checked_add_days(Days::new(days));
+    }
+    if let Ok(weekday) = value.parse::<Weekday>() {
+        let ahead = (7 + weekday.num_days_from_monday() as i64
+            - today.weekday().num_days_from_monday() as i64)
+            % 7;
+        let ahead = if ahead == 0 { 7 } else { ahead };
+        return today.checked_add_days(Days::new(ahead as u64));
+    }
+    parse_date(&value)
+}
+
+fn parse_annotation(word: &str) -> Option<(&str, &str)> {
+    let (key, value) = word.split_once(':')?;
+    let valid = !key.is_empty()
+        && key.chars().all(|c| c.is_ascii_lowercase())
+        && !value.is_empty()
+        && !value.starts_with("//");
+    valid.then_some((key, value))
+}
+
+fn parse_priority(word: &str) -> Option<char> {
+    let inner = word.strip_prefix('(')?.strip_suffix(')')?;
+    let mut chars = inner.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_uppercase() => Some(c),
+        _ => None,
+    }
+}
+
+impl Task {
+    pub fn new(path: PathBuf, rel_path: String, line: usize, done: bool, text: &str) -> Task {
+        let mut task = Task {
+            id: String::new(),
+            path,
+            rel_path,
+            line,
+            done,
+            text: text.to_string(),
+            description: String::new(),
+            priority: None,
+            due: None,
+            project: None,
+            people: Vec::new(),
+            projects: Vec::new(),
+            tags: Vec::new(),
+            annotations: BTreeMap::new(),
+        };
+
+        let mut words = Vec::new();
+        for word in text.split_whitespace() {
+            if let Some(priority) = parse_priority(word) {
+                task.priority = Some(priority);
+                continue;
+            }
+            if let Some((key, value)) = parse_annotation(word) {
+                task.annotations.insert(key.to_string(), value.to_string());
+                continue;
+            }
+            if let Some(person) = word.strip_prefix('@').filter(|p| !p.is_empty()) {
+                task.people.push(person.to_string());
+            } else if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+                task.projects.push(project.to_string());
+            } else if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+                task.tags.push(tag.to_lowercase());
+            }
+            words.push(word);
+        }
+        task.description = words.join(" ");
+        task.due = task.annotations.get("due").and_then(|d| parse_date(d));
+        task
+    }
+
+    pub fn in_project(&self, project: &str) -> bool {
+        self.project.as_deref() == Some(project) || self.projects.iter().any(|p| p == project)
+    }
+}
+
+// Ids hash the task text without its completion date, sync uuid and board status, so they
+// survive `done`, moves, exports and board moves.
+fn base_id(task: &Task) -> String {
+    let text: Vec<&str> = task
+        .text
+        .split_whitespace()
+        .filter(|w| !w.starts_with("done:") && !w.starts_with("uuid:") && !w.starts_with("status:"))
+        .collect();
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.join(" ").bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)[..7].to_string()
+}
+
+fn assign_ids(tasks: &mut [Task]) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for task in tasks.iter_mut() {
+        let base = base_id(task);
+        let count = seen.entry(base.clone()).or_insert(0);
+        *count += 1;
+        task.id = if *count == 1 {
+            base
+        } else {
+            format!("{}-{}", base, count)
+        };
+    }
+}
+
+pub fn find<'a>(tasks: &'a [Task], id: &str) -> Result<&'a Task, io::Error> {
+    let matches: Vec<&Task> = tasks.iter().filter(|t| t.id.starts_with(id)).collect();
+    match matches.as_slice() {
+        [task] => Ok(task),
+        [] => Err(io::Error::new(io::ErrorKind::NotFound, "Task not found.")),
+        _ => match matches.iter().find(|t| t.id == id) {
+            Some(task) => Ok(task),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Task id is ambiguous.",
+            )),
+        },
+    }
+}
+
+pub fn write_note(path: &Path, content: &str) -> Result<(), io::Error> {
+    match meta::touch(content)? {
+        Some(touched) => fs::write(path, touched),
+        None => fs::write(path, content),
+    }
+}
+
+pub fn replace_line(path: &Path, line: usize, replacement: Option<&str>) -> Result<(), io::Error> {
+    match replacement {
+        Some(text) => replace_lines(path, &[(line, text.to_string())]),
+        None => {
+            let content = fs::read_to_string(path)?;
+            let mut lines: Vec<&str> = content.lines().collect();
+            if line == 0 || line > lines.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Task line no longer exists.",
+                ));
+            }
+            lines.remove(line - 1);
+            let mut updated = lines.join("\n");
+            if content.ends_with('\n') {
+                updated.push('\n');
+            }
+            write_note(path, &updated)
+        }
+    }
+}
+
+pub fn replace_lines(path: &Path, replacements: &[(usize, String)]) -> Result<(), io::Error> {
+    let content = fs::read_to_string(path)?;
+    let mut lines: Vec<&str> = content.lines().collect();
+    for (line, text) in replacements {
+        if *line == 0 || *line > lines.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Task line no longer exists.",
+            ));
+        }
+        lines[line - 1] = text;
+    }
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    write_note(path, &updated)
+}
+
+pub fn complete_line(line: &str, date: NaiveDate) -> Option<String> {
+    let index = line.find("[ ]")?;
+    Some(format!(
+        "{}[x]{} done:{}",
+        &line[..index],
+        line[index + 3..].trim_end(),
+        date.format("%Y-%m-%d")
+    ))
+}
+
+pub fn reopen_line(line: &str) -> Option<String> {
+    let index = line.find("[x]").or_else(|| line.find("[X]"))?;
+    let rest: Vec<&str> = line[index + 3..]
+        .split_whitespace()
+        .filter(|w| !w.starts_with("done:"))
+        .collect();
+    Some(format!("{}[ ] {}", &line[..index], rest.join(" ")))
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+// The number of lines after `index` nested below it.
+pub fn nested(lines: &[&str], index: usize) -> usize {
+    let depth = indent(lines[index]);
+    lines[index + 1..]
+        .iter()
+        .take_while(|l| !l.trim().is_empty() && indent(l) > depth)
+        .count()
+}
+
+// New tasks go below the last task of the note and its sub-items, or at its end if it has
+// none yet.
+pub fn insert_task(path: &Path, task_line: &str) -> Result<(), io::Error> {
+    insert_tasks(path, &[task_line.to_string()])
+}
+
+pub fn insert_tasks(path: &Path, task_lines: &[String]) -> Result<(), io::Error> {
+    let content = fs::read_to_string(path)?;
+    let mut lines: Vec<&str> = content.lines().collect();
+    let position = lines
+        .iter()
+        .rposition(|l| crate::core::markdown::checkbox(l).is_some())
+        .map(|i| i + 1 + nested(&lines, i))
+        .unwrap_or(lines.len());
+    lines.splice(position..position, task_lines.iter().map(String::as_str));
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    write_note(path, &updated)
+}
+
+/// Removes a task together with its nested sub-items and returns them, unindented so the
+/// task is at the top level.
+pub fn take_task(path: &Path, line: usize) -> Result<Vec<String>, io::Error> {
+    let content = fs::read_to_string(path)?;
+    let mut lines: Vec<&str> = content.lines().collect();
+    if line == 0 || line > lines.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Task line no longer exists.",
+        ));
+    }
+    let depth = indent(lines[line - 1]);
+    let end = line + nested(&lines, line - 1);
+    let taken = lines
+        .drain(line - 1..end)
+        .map(|l| l.get(depth..).unwrap_or(l.trim_start()).to_string())
+        .collect();
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    write_note(path, &updated)?;
+    Ok(taken)
+}
+
+pub fn collect(notebook: &Notebook) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    for note in &notebook.notes {
+        for item in note.checkboxes() {
+            let mut task = Task::new(
+                note.path.clone(),
+                note.rel_path.clone(),
+                item.line,
+                item.done,
+                &item.text,
+            );
+            task.project = note
+                .project
+                .clone()
+                .or_else(|| task.projects.first().cloned());
+            tasks.push(task);
+        }
+    }
+    assign_ids(&mut tasks);
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_relative_dates() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        let date = |value| parse_relative_date(value, today);
+        assert_eq!(date("+3d"), NaiveDate::from_ymd_opt(2024, 3, 8));
+        assert_eq!(date("+2w"), NaiveDate::from_ymd_opt(2024, 3, 19));
+        assert_eq!(date("friday"), NaiveDate::from_ymd_opt(2024, 3, 8));
+        assert_eq!(date("+3é"), None);
+        assert_eq!(date("+é"), None);
+        assert_eq!(date("+"), None);
+        assert_eq!(date("+18446744073709551615w"), None);
+        assert_eq!(date("+18446744073709551615d"), None);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::core::tasks::{self, Task};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io;
+
+#[derive(Serialize, Deserialize)]
+pub struct TwTask {
+    pub uuid: String,
+    pub description: String,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+}
+
+impl TwTask {
+    // Taskwarrior dues carry a time, grom only keeps the local date.
+    pub fn due_date(&self) -> Option<NaiveDate> {
+        self.due.as_deref().and_then(parse_date)
+    }
+}
+
+fn format_date(date: NaiveDate) -> String {
+    let local = Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .map(|d| d.with_timezone(&Utc).naive_utc())
+        .unwrap_or_default();
+    local.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let utc = NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S").ok()?;
+    Some(
+        Utc.from_utc_datetime(&utc)
+            .with_timezone(&Local)
+            .date_naive(),
+    )
+}
+
+fn priority_to_tw(priority: char) -> &'static str {
+    match priority {
+        'A' => "H",
+        'B' => "M",
+        _ => "L",
+    }
+}
+
+fn priority_from_tw(priority: &str) -> Option<char> {
+    match priority {
+        "H" => Some('A'),
+        "M" => Some('B'),
+        "L" => Some('C'),
+        _ => None,
+    }
+}
+
+/// The `uuid:` of a task, or one derived from its id for tasks that have none yet. Exporting
+/// does not have to write uuids into the notes, and gives the same ones every time.
+pub fn task_uuid(task: &Task) -> String {
+    if let Some(uuid) = task.annotations.get("uuid") {
+        return uuid.clone();
+    }
+    let hash = Sha256::digest(format!("grom-task:{}", task.id).as_bytes());
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    uuid::Builder::from_custom_bytes(bytes)
+        .into_uuid()
+        .to_string()
+}
+
+pub fn from_task(task: &Task, entry: NaiveDate) -> TwTask {
+    let description: Vec<&str> = task
+        .description
+        .split_whitespace()
+        .filter(|w| !(w.starts_with('#') && w.len() > 1))
+        .collect();
+    TwTask {
+        uuid: task_uuid(task),
+        description: description.join(" "),
+        status: if task.done { "completed" } else { "pending" }.to_string(),
+        project: task.project.clone(),
+        tags: task.tags.clone(),
+        priority: task.priority.map(|p| priority_to_tw(p).to_string()),
+        due: task.due.map(format_date),
+        entry: Some(format_date(entry)),
+        end: task
+            .annotations
+            .get("done")
+            .and_then(|d| tasks::parse_date(d))
+            .map(format_date),
+    }
+}
+
+// `project` is the grom project the line is filed under; other projects become `+project`.
+pub fn to_markdown(tw: &TwTask, project: Option<&str>) -> String {
+    to_line(tw, project, &[])
+}
+
+/// The line of `task` with the fields of its updated Taskwarrior version. Annotations
+/// Taskwarrior does not know about, like `rec:` or `status:`, are kept.
+pub fn update_line(task: &Task, tw: &TwTask, project: Option<&str>) -> String {
+    let kept: Vec<&str> = task
+        .text
+        .split_whitespace()
+        .filter(|w| {
+            w.split_once(':').is_some_and(|(key, value)| {
+                !["due", "done", "uuid"].contains(&key)
+                    && task.annotations.get(key).map(String::as_str) == Some(value)
+            })
+        })
+        .collect();
+    to_line(tw, project, &kept)
+}
+
+fn to_line(tw: &TwTask, project: Option<&str>, annotations: &[&str]) -> String {
+    let mut words: Vec<String> = Vec::new();
+    if let Some(priority) = tw.priority.as_deref().and_then(priority_from_tw) {
+        words.push(format!("({})", priority));
+    }
+    words.push(tw.description.trim().to_string());
+    if let Some(tw_project) = &tw.project {
+        if project != Some(tw_project.as_str()) {
+            words.push(format!("+{}", tw_project));
+        }
+    }
+    for tag in &tw.tags {
+        words.push(format!("#{}", tag));
+    }
+    words.extend(annotations.iter().map(|a| a.to_string()));
+    if let Some(due) = tw.due.as_deref().and_then(parse_date) {
+        words.push(format!("due:{}", due.format("%Y-%m-%d")));
+    }
+    if let Some(end) = tw.end.as_deref().and_then(parse_date) {
+        if tw.status == "completed" {
+            words.push(format!("done:{}", end.format("%Y-%m-%d")));
+        }
+    }
+    words.push(format!("uuid:{}", tw.uuid));
+    format!(
+        "- [{}] {}",
+        if tw.status == "completed" { "x" } else { " " },
+        words.join(" ")
+    )
+}
+
+pub fn parse(content: &str) -> Result<Vec<TwTask>, io::Error> {
+    let invalid = |e: serde_json::Error| io::Error::new(io::ErrorKind::InvalidData, e.to_string());
+    if content.trim_start().starts_with('[') {
+        return serde_json::from_str(content).map_err(invalid);
+    }
+    content
+        .lines()
+        .map(|l| l.trim().trim_end_matches(','))
+        .filter(|l| !l.is_empty())
+        .map(|l| serde_json::from_str(l).map_err(invalid))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn task(text: &str) -> Task {
+        Task::new(
+            PathBuf::from("inbox.md"),
+            "inbox.md".to_string(),
+            1,
+            false,
+            text,
+        )
+    }
+
+    #[test]
+    fn updates_keep_grom_annotations() {
+        let entry = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let existing = task("Water plants #home rec:weekly status:doing due:2024-03-05 uuid:abc");
+        let mut tw = from_task(&existing, entry);
+        assert_eq!(tw.description, "Water plants");
+        tw.description = "Water the plants".to_string();
+        tw.due = Some(format_date(NaiveDate::from_ymd_opt(2024, 3, 12).unwrap()));
+
+        let line = update_line(&existing, &tw, None);
+        assert_eq!(
+            line,
+            "- [ ] Water the plants #home rec:weekly status:doing due:2024-03-12 uuid:abc"
+        );
+        let updated = task(line.trim_start_matches("- [ ] "));
+        assert_eq!(
+            updated.annotations.get("rec").map(String::as_str),
+            Some("weekly")
+        );
+        let round_trip = from_task(&updated, entry);
+        assert_eq!(round_trip.description, tw.description);
+        assert_eq!(round_trip.due_date(), tw.due_date());
+    }
+
+    #[test]
+    fn derives_stable_uuids() {
+        let mut first = task("Call the bank");
+        first.id = "3ce92a4".to_string();
+        let mut again = task("Call the bank");
+        again.id = "3ce92a4".to_string();
+        assert_eq!(task_uuid(&first), task_uuid(&again));
+        assert!(uuid::Uuid::parse_str(&task_uuid(&first)).is_ok());
+        assert_eq!(task_uuid(&task("Call the bank uuid:abc")), "abc");
+    }
+}
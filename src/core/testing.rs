@@ -0,0 +1,47 @@
+use crate::core::notes::Notebook;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// A notebook in its own temporary directory, removed again when dropped.
+pub struct TempNotebook {
+    root: PathBuf,
+}
+
+impl TempNotebook {
+    pub fn new(files: &[(&str, &str)]) -> TempNotebook {
+        let root = std::env::temp_dir().join(format!(
+            "grom-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        TempNotebook { root }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn dir(&self) -> &str {
+        self.root.to_str().unwrap()
+    }
+
+    pub fn load(&self) -> Notebook {
+        Notebook::load(self.dir()).unwrap()
+    }
+}
+
+impl Drop for TempNotebook {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
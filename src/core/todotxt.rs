@@ -0,0 +1,183 @@
+use crate::core::tasks::{self, Task};
+use chrono::NaiveDate;
+
+pub struct Item {
+    pub done: bool,
+    pub completed: Option<NaiveDate>,
+    pub priority: Option<char>,
+    pub text: String,
+}
+
+fn strip_priority(text: &str) -> (Option<char>, &str) {
+    let bytes = text.as_bytes();
+    if bytes.len() >= 4
+        && bytes[0] == b'('
+        && bytes[1].is_ascii_uppercase()
+        && bytes[2] == b')'
+        && bytes[3] == b' '
+    {
+        (Some(bytes[1] as char), &text[4..])
+    } else {
+        (None, text)
+    }
+}
+
+fn strip_date(text: &str) -> (Option<NaiveDate>, &str) {
+    match text.split_once(' ') {
+        Some((first, rest)) => match tasks::parse_date(first) {
+            Some(date) => (Some(date), rest),
+            None => (None, text),
+        },
+        None => (None, text),
+    }
+}
+
+pub fn parse_line(line: &str) -> Option<Item> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let (done, rest) = match line.strip_prefix("x ") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (mut priority, rest) = strip_priority(rest);
+    let (completed, rest) = if done { strip_date(rest) } else { (None, rest) };
+    let (_created, rest) = strip_date(rest);
+
+    let mut words = Vec::new();
+    for word in rest.split_whitespace() {
+        match word.strip_prefix("pri:") {
+            Some(p) if p.len() == 1 && p.chars().all(|c| c.is_ascii_uppercase()) => {
+                priority = p.chars().next()
+            }
+            _ => words.push(word),
+        }
+    }
+    Some(Item {
+        done,
+        completed,
+        priority,
+        text: words.join(" "),
+    })
+}
+
+pub fn to_line(task: &Task) -> String {
+    let mut words: Vec<String> = Vec::new();
+    if task.done {
+        words.push("x".to_string());
+        if let Some(done) = task.annotations.get("done") {
+            words.push(done.clone());
+        }
+    } else if let Some(priority) = task.priority {
+        words.push(format!("({})", priority));
+    }
+    words.push(task.description.clone());
+    if let Some(project) = &task.project {
+        if !task.projects.contains(project) {
+            words.push(format!("+{}", project));
+        }
+    }
+    for (key, value) in &task.annotations {
+        if key != "done" {
+            words.push(format!("{}:{}", key, value));
+        }
+    }
+    if task.done {
+        if let Some(priority) = task.priority {
+            words.push(format!("pri:{}", priority));
+        }
+    }
+    words.join(" ")
+}
+
+// `+project` is dropped again when it names the project the task is filed under.
+pub fn to_markdown(item: &Item, project: Option<&str>) -> String {
+    let mut words: Vec<String> = Vec::new();
+    if let Some(priority) = item.priority {
+        words.push(format!("({})", priority));
+    }
+    for word in item.text.split_whitespace() {
+        if project.is_some_and(|p| word.strip_prefix('+') == Some(p)) {
+            continue;
+        }
+        words.push(word.to_string());
+    }
+    if let Some(completed) = item.completed {
+        words.push(format!("done:{}", completed.format("%Y-%m-%d")));
+    }
+    format!(
+        "- [{}] {}",
+        if item.done { "x" } else { " " },
+        words.join(" ")
+    )
+}
+
+// Matching key for deduplication: the text without priority, projects and annotations.
+pub fn key(text: &str) -> String {
+    text.split_whitespace()
+        .filter(|w| !w.starts_with('+'))
+        .filter(|w| !(w.starts_with('(') && w.ends_with(')') && w.len() == 3))
+        .filter(|w| {
+            !w.split_once(':').is_some_and(|(k, v)| {
+                !k.is_empty() && k.chars().all(|c| c.is_ascii_lowercase()) && !v.is_empty()
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // todo.txt -> markdown task line -> todo.txt
+    fn round_trip(line: &str, project: Option<&str>) -> (String, String) {
+        let item = parse_line(line).unwrap();
+        let markdown = to_markdown(&item, project);
+        let text = markdown.split_once("] ").unwrap().1;
+        let mut task = Task::new(Default::default(), String::new(), 1, item.done, text);
+        task.project = project
+            .map(String::from)
+            .or_else(|| task.projects.first().cloned());
+        (markdown, to_line(&task))
+    }
+
+    #[test]
+    fn parses_lines() {
+        let item = parse_line("x (B) 2024-03-02 2024-03-01 ship it +acme @office").unwrap();
+        assert!(item.done);
+        assert_eq!(item.priority, Some('B'));
+        assert_eq!(item.completed, NaiveDate::from_ymd_opt(2024, 3, 2));
+        assert_eq!(item.text, "ship it +acme @office");
+        assert!(parse_line("   ").is_none());
+    }
+
+    #[test]
+    fn round_trips_open_tasks() {
+        let (markdown, line) = round_trip("(A) 2024-03-01 call bob +acme due:2024-03-10", None);
+        assert_eq!(markdown, "- [ ] (A) call bob +acme due:2024-03-10");
+        assert_eq!(line, "(A) call bob +acme due:2024-03-10");
+    }
+
+    #[test]
+    fn round_trips_done_tasks() {
+        let (markdown, line) = round_trip("x 2024-03-02 pay rent pri:B", None);
+        assert_eq!(markdown, "- [x] (B) pay rent done:2024-03-02");
+        assert_eq!(line, "x 2024-03-02 pay rent pri:B");
+    }
+
+    #[test]
+    fn drops_the_filed_project() {
+        let (markdown, line) = round_trip("write docs +grom", Some("grom"));
+        assert_eq!(markdown, "- [ ] write docs");
+        assert_eq!(line, "write docs +grom");
+    }
+
+    #[test]
+    fn keys_ignore_metadata() {
+        assert_eq!(key("(A) Call Bob +acme due:2024-03-10"), key("call bob"));
+        assert_ne!(key("call bob"), key("call alice"));
+    }
+}
@@ -1,21 +1,46 @@
+use crate::core::meta;
 use std::{
     fs::{self, OpenOptions},
-    io::{self, ErrorKind},
+    io::{self, Write},
     path::Path,
     process::Command,
 };
 
-pub fn save_file(file: &str) -> Result<(), io::Error> {
+pub fn save_file(file: &str, content: &str) -> Result<(), io::Error> {
     OpenOptions::new()
         .write(true)
         .create_new(true)
-        .open(file)?;
+        .open(file)?
+        .write_all(content.as_bytes())
+}
+
+fn edit(cmd: &str, args: &[String], file: &str) -> Result<(), io::Error> {
+    let before = fs::read_to_string(file).ok();
+    Command::new(cmd).args(args).arg(file).status()?;
+    let after = fs::read_to_string(file).ok();
+    if let Some(after) = after.filter(|a| Some(a) != before.as_ref()) {
+        if let Some(touched) = meta::touch(&after)? {
+            fs::write(file, touched)?;
+        }
+    }
     Ok(())
 }
 
 pub fn open_file(cmd: &str, file: &str) -> Result<(), io::Error> {
-    Command::new(cmd).arg(file).status()?;
-    Ok(())
+    edit(cmd, &[], file)
+}
+
+pub fn open_file_at(cmd: &str, file: &str, line: usize) -> Result<(), io::Error> {
+    let program = Path::new(cmd)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(cmd);
+    match program {
+        "nvim" | "vim" | "vi" | "nano" | "emacs" | "kak" | "micro" => {
+            edit(cmd, &[format!("+{}", line)], file)
+        }
+        _ => edit(cmd, &[], file),
+    }
 }
 
 pub fn ensure_all_dirs(path: &str) -> Result<(), io::Error> {
@@ -34,22 +59,37 @@ pub fn path_exists<P: AsRef<Path>>(path: P) -> bool {
     path.exists()
 }
 
+// Projects created by older versions of grom start with a hidden `.start.md`.
+pub fn project_start(note_dir: &str, project: &str) -> String {
+    let dir = format!("{}/projects/{}", note_dir, project);
+    let legacy = format!("{}/.start.md", dir);
+    if !path_exists(format!("{}/start.md", dir)) && path_exists(&legacy) {
+        legacy
+    } else {
+        format!("{}/start.md", dir)
+    }
+}
+
 pub fn find_projects(dir: &str) -> Result<Vec<(String, String)>, io::Error> {
     let mut projects = Vec::new();
     let dir = Path::new(&dir);
 
-    let entries = fs::read_dir(dir).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    let entries = fs::read_dir(dir).map_err(io::Error::other)?;
 
     for entry in entries.filter_map(Result::ok) {
         let path = entry.path();
         if path.is_dir() {
-            let start_md_path = path.join("start.md");
-            if start_md_path.exists() {
+            let start_md_path = [path.join("start.md"), path.join(".start.md")]
+                .into_iter()
+                .find(|p| p.exists());
+            if let Some(start_md_path) = start_md_path {
                 if let Some(parent) = path.file_name().and_then(|n| n.to_str()) {
-                    projects.push((
-                        String::from(start_md_path.to_str().unwrap()),
-                        parent.to_string(),
-                    ))
+                    let title = fs::read_to_string(&start_md_path)
+                        .ok()
+                        .and_then(|c| meta::parse(&c))
+                        .and_then(|(_, m)| m.title)
+                        .unwrap_or(parent.to_string());
+                    projects.push((String::from(start_md_path.to_str().unwrap()), title))
                 }
             }
         }
@@ -76,5 +116,35 @@ pub fn select_project(note_dir: &str) -> Result<String, io::Error> {
     cliclack::select("Select a Project".to_string())
         .items(&items)
         .interact()
-        .map_err(|_| io::Error::new(ErrorKind::Other, "Error selecting project"))
+        .map_err(|_| io::Error::other("Error selecting project"))
+}
+
+pub fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+    let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+    for row in std::iter::once(&header).chain(rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+pub fn slugify(value: &str) -> String {
+    let mut slug = String::new();
+    for c in value.to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
 }
@@ -1,6 +1,9 @@
 use std::process;
 use clap::{Parser, Subcommand};
-use grom::commands::{diary, project, quick_note, sync};
+use grom::commands::{
+    agenda, backup, board, diary, export, graph, import, meetings, project, publish, query,
+    quick_note, sql, sync, tasks,
+};
 use grom::core::config;
 
 #[derive(Parser)]
@@ -25,12 +28,206 @@ enum Command {
         project_name: String,
     },
     Today {},
+    Diary {
+        #[command(subcommand)]
+        command: DiaryCommand,
+    },
     Week {},
     Month {},
     Sync {
         #[command(subcommand)]
         command: SyncCommand,
     },
+    Graph {
+        #[arg(short, long, default_value = "dot", value_parser = ["dot", "json", "graphml"])]
+        format: String,
+    },
+    Query {
+        #[arg(value_name = "QUERY")]
+        query: String,
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json", "picker"])]
+        format: String,
+    },
+    Sql {
+        #[arg(value_name = "SQL")]
+        query: String,
+        #[arg(short, long, default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+    },
+    Export {
+        #[command(subcommand)]
+        command: ExportCommand,
+    },
+    Import {
+        #[command(subcommand)]
+        command: ImportCommand,
+    },
+    Backup {
+        #[arg(short, long, value_name = "FILE")]
+        out: Option<String>,
+    },
+    Restore {
+        #[arg(value_name = "ARCHIVE")]
+        archive: String,
+        #[arg(short, long, value_name = "DIR")]
+        to: Option<String>,
+    },
+    Meetings {
+        #[arg(long, value_name = "FILE")]
+        ics: String,
+        #[arg(short, long, default_value = "today")]
+        date: String,
+    },
+    Agenda {
+        #[arg(short, long)]
+        week: bool,
+        #[arg(short, long, default_value = "list", value_parser = ["list", "picker"])]
+        format: String,
+    },
+    Publish {
+        #[arg(value_name = "OUT_DIR")]
+        out_dir: String,
+        #[arg(short, long, default_value = "html", value_parser = ["html", "markdown"])]
+        format: String,
+    },
+    Board {
+        #[arg(value_name = "PROJECT_NAME")]
+        project_name: String,
+        #[arg(short, long = "move", num_args = 2, value_names = ["ID", "COLUMN"])]
+        move_to: Option<Vec<String>>,
+        #[arg(short, long)]
+        interactive: bool,
+    },
+    Tasks {
+        #[command(subcommand)]
+        command: Option<TasksCommand>,
+        #[arg(short, long)]
+        project: Option<String>,
+        #[arg(short, long)]
+        tag: Option<String>,
+        #[arg(short, long, value_name = "overdue|today|week|month|none|YYYY-MM-DD")]
+        due: Option<String>,
+        #[arg(short, long, default_value = "open", value_parser = ["open", "done", "all"])]
+        status: String,
+        #[arg(short, long, default_value = "list", value_parser = ["list", "json", "picker"])]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DiaryCommand {
+    Compile {
+        #[arg(long, value_name = "YYYY-MM", group = "period")]
+        month: Option<String>,
+        #[arg(long, value_name = "YYYY", group = "period")]
+        year: Option<String>,
+        #[arg(long, value_name = "YYYY-Www", group = "period")]
+        week: Option<String>,
+        #[arg(long, value_name = "START..END", group = "period")]
+        range: Option<String>,
+        #[arg(short, long, default_value = "markdown", value_parser = ["markdown", "html"])]
+        format: String,
+        #[arg(short, long, value_name = "FILE")]
+        out: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommand {
+    Ics {
+        #[arg(short, long, value_name = "FILE")]
+        out: Option<String>,
+    },
+    Html {
+        #[arg(value_name = "OUT_DIR")]
+        out_dir: String,
+    },
+    Epub {
+        #[arg(short, long, value_name = "PROJECT_NAME", group = "source")]
+        project: Option<String>,
+        #[arg(short, long, value_name = "YYYY|YYYY-MM", group = "source")]
+        diary: Option<String>,
+        #[arg(short, long, value_name = "FILE")]
+        out: Option<String>,
+    },
+    Feed {
+        #[arg(short, long, value_name = "FILE")]
+        out: Option<String>,
+        #[arg(short, long, value_parser = ["daily", "weekly", "monthly"])]
+        period: Option<String>,
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    Json {
+        #[arg(long)]
+        ndjson: bool,
+        #[arg(short, long, value_name = "FILE")]
+        out: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportCommand {
+    Obsidian {
+        #[arg(value_name = "VAULT")]
+        vault: String,
+    },
+    Jrnl {
+        #[arg(value_name = "FILE")]
+        file: String,
+    },
+    Dayone {
+        #[arg(value_name = "EXPORT")]
+        export: String,
+    },
+    Enex {
+        #[arg(value_name = "FILE")]
+        file: String,
+        #[arg(short, long, value_name = "FILE")]
+        report: Option<String>,
+    },
+    Joplin {
+        #[arg(value_name = "JEX")]
+        file: String,
+        #[arg(short, long, value_name = "FILE")]
+        report: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TasksCommand {
+    Done {
+        #[arg(value_name = "ID")]
+        id: String,
+    },
+    Add {
+        #[arg(value_name = "PROJECT_NAME")]
+        project_name: String,
+        #[arg(value_name = "TEXT")]
+        text: String,
+        #[arg(short, long, value_name = "DATE")]
+        due: Option<String>,
+    },
+    Move {
+        #[arg(value_name = "ID")]
+        id: String,
+        #[arg(value_name = "PROJECT_NAME")]
+        project_name: String,
+    },
+    Export {
+        #[arg(short, long, value_parser = ["todotxt", "taskwarrior"])]
+        format: String,
+        #[arg(short, long, value_name = "FILE")]
+        out: Option<String>,
+    },
+    Import {
+        #[arg(value_name = "FILE")]
+        file: String,
+        #[arg(short, long, default_value = "todotxt", value_parser = ["todotxt", "taskwarrior"])]
+        format: String,
+        #[arg(short, long, value_name = "PROJECT_NAME")]
+        project: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -43,12 +240,22 @@ enum SyncCommand {
         #[arg(value_name = "MESSAGE")]
         message: String,
     },
-    Pull {},
+    Pull {
+        #[arg(long)]
+        rebase: bool,
+    },
 }
 
 fn main() {
     ctrlc::set_handler(move || {}).expect("settings ctrl-c handler");
     let cli = Cli::parse();
+    // Restoring has to work on a machine without a config.
+    if let Some(Command::Restore { archive, to }) = &cli.command {
+        if let Err(e) = backup::restore(archive.clone(), to.clone()) {
+            cliclack::note("T_T", format!("Unable to restore backup: {e}")).unwrap();
+        }
+        return;
+    }
     let config = match config::load_config() {
         Ok(cfg) => cfg,
         Err(_) => {
@@ -69,6 +276,26 @@ fn main() {
                     cliclack::note("T_T", "Unable to create daily diary").unwrap();
                 }
             },
+            Command::Diary { command } => match command {
+                DiaryCommand::Compile {
+                    month,
+                    year,
+                    week,
+                    range,
+                    format,
+                    out,
+                } => {
+                    let period = diary::Period {
+                        month: month.clone(),
+                        year: year.clone(),
+                        week: week.clone(),
+                        range: range.clone(),
+                    };
+                    if let Err(e) = diary::compile(period, format.clone(), out.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to compile diary: {e}")).unwrap();
+                    }
+                }
+            },
             Command::Week {} => {
                 if diary::weekly_diary(config).is_err() {
                     cliclack::note("T_T", "Unable to create weekly diary").unwrap();
@@ -84,21 +311,188 @@ fn main() {
                     cliclack::note("T_T", "Unable to create project").unwrap();
                 }
             },
+            Command::Graph { format } => {
+                if graph::graph(format.clone(), config).is_err() {
+                    cliclack::note("T_T", "Unable to export graph.").unwrap();
+                }
+            }
+            Command::Query { query, format } => {
+                if let Err(e) = query::query(query.clone(), format.clone(), config) {
+                    cliclack::note("T_T", format!("Unable to run query: {e}")).unwrap();
+                }
+            }
+            Command::Sql { query, format } => {
+                if let Err(e) = sql::sql(query.clone(), format.clone(), config) {
+                    cliclack::note("T_T", format!("Unable to run SQL: {e}")).unwrap();
+                }
+            }
+            Command::Export { command } => match command {
+                ExportCommand::Ics { out } => {
+                    if let Err(e) = export::ics(out.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to export calendar: {e}")).unwrap();
+                    }
+                }
+                ExportCommand::Json { ndjson, out } => {
+                    if let Err(e) = export::json(*ndjson, out.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to export notes: {e}")).unwrap();
+                    }
+                }
+                ExportCommand::Html { out_dir } => {
+                    if let Err(e) = export::html(out_dir.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to export site: {e}")).unwrap();
+                    }
+                }
+                ExportCommand::Epub {
+                    project,
+                    diary,
+                    out,
+                } => {
+                    if let Err(e) =
+                        export::epub(project.clone(), diary.clone(), out.clone(), config)
+                    {
+                        cliclack::note("T_T", format!("Unable to export EPUB: {e}")).unwrap();
+                    }
+                }
+                ExportCommand::Feed { out, period, limit } => {
+                    if let Err(e) = export::feed(out.clone(), period.clone(), *limit, config) {
+                        cliclack::note("T_T", format!("Unable to export feed: {e}")).unwrap();
+                    }
+                }
+            },
+            Command::Import { command } => match command {
+                ImportCommand::Obsidian { vault } => {
+                    if let Err(e) = import::obsidian(vault.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to import vault: {e}")).unwrap();
+                    }
+                }
+                ImportCommand::Jrnl { file } => {
+                    if let Err(e) = import::jrnl(file.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to import journal: {e}")).unwrap();
+                    }
+                }
+                ImportCommand::Dayone { export } => {
+                    if let Err(e) = import::dayone(export.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to import journal: {e}")).unwrap();
+                    }
+                }
+                ImportCommand::Enex { file, report } => {
+                    if let Err(e) = import::enex(file.clone(), report.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to import notes: {e}")).unwrap();
+                    }
+                }
+                ImportCommand::Joplin { file, report } => {
+                    if let Err(e) = import::joplin(file.clone(), report.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to import notes: {e}")).unwrap();
+                    }
+                }
+            },
+            Command::Backup { out } => {
+                if let Err(e) = backup::backup(out.clone(), config) {
+                    cliclack::note("T_T", format!("Unable to create backup: {e}")).unwrap();
+                }
+            }
+            Command::Restore { .. } => {}
+            Command::Meetings { ics, date } => {
+                if let Err(e) = meetings::meetings(ics.clone(), date.clone(), config) {
+                    cliclack::note("T_T", format!("Unable to create meeting notes: {e}")).unwrap();
+                }
+            }
+            Command::Agenda { week, format } => {
+                if let Err(e) = agenda::agenda(*week, format.clone(), config) {
+                    cliclack::note("T_T", format!("Unable to show agenda: {e}")).unwrap();
+                }
+            }
+            Command::Publish { out_dir, format } => {
+                if let Err(e) = publish::publish(out_dir.clone(), format.clone(), config) {
+                    cliclack::note("T_T", format!("Unable to publish notes: {e}")).unwrap();
+                }
+            }
+            Command::Board {
+                project_name,
+                move_to,
+                interactive,
+            } => {
+                if let Err(e) =
+                    board::board(project_name.clone(), move_to.clone(), *interactive, config)
+                {
+                    cliclack::note("T_T", format!("Unable to show board: {e}")).unwrap();
+                }
+            }
+            Command::Tasks {
+                command: Some(command),
+                ..
+            } => match command {
+                TasksCommand::Done { id } => {
+                    if let Err(e) = tasks::done(id.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to complete task: {e}")).unwrap();
+                    }
+                }
+                TasksCommand::Add {
+                    project_name,
+                    text,
+                    due,
+                } => {
+                    if let Err(e) =
+                        tasks::add(project_name.clone(), text.clone(), due.clone(), config)
+                    {
+                        cliclack::note("T_T", format!("Unable to add task: {e}")).unwrap();
+                    }
+                }
+                TasksCommand::Move { id, project_name } => {
+                    if let Err(e) = tasks::move_task(id.clone(), project_name.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to move task: {e}")).unwrap();
+                    }
+                }
+                TasksCommand::Export { format, out } => {
+                    if let Err(e) = tasks::export(format.clone(), out.clone(), config) {
+                        cliclack::note("T_T", format!("Unable to export tasks: {e}")).unwrap();
+                    }
+                }
+                TasksCommand::Import {
+                    file,
+                    format,
+                    project,
+                } => {
+                    if let Err(e) =
+                        tasks::import(file.clone(), format.clone(), project.clone(), config)
+                    {
+                        cliclack::note("T_T", format!("Unable to import tasks: {e}")).unwrap();
+                    }
+                }
+            },
+            Command::Tasks {
+                command: None,
+                project,
+                tag,
+                due,
+                status,
+                format,
+            } => {
+                let filter = tasks::Filter {
+                    project: project.clone(),
+                    tag: tag.clone(),
+                    due: due.clone(),
+                    status: status.clone(),
+                };
+                if let Err(e) = tasks::list(filter, format.clone(), config) {
+                    cliclack::note("T_T", format!("Unable to list tasks: {e}")).unwrap();
+                }
+            }
             Command::Sync { command } => {
                 match command {
                     SyncCommand::Init { remote_url } => {
-                        if sync::init(remote_url.clone(), config).is_err() {
-                            cliclack::note("T_T", "Unable to initialize sync.").unwrap();
+                        if let Err(e) = sync::init(remote_url.clone(), config) {
+                            cliclack::note("T_T", format!("Unable to initialize sync: {e}")).unwrap();
                         }
                     }
                     SyncCommand::Push { message } => {
-                        if sync::push(message.clone(), config).is_err() {
-                            cliclack::note("T_T", "Unable to push changes.").unwrap();
+                        if let Err(e) = sync::push(message.clone(), config) {
+                            cliclack::note("T_T", format!("Unable to push changes: {e}")).unwrap();
                         }
                     }
-                    SyncCommand::Pull {} => {
-                        if sync::pull(config).is_err() {
-                            cliclack::note("T_T", "Unable to pull changes.").unwrap();
+                    SyncCommand::Pull { rebase } => {
+                        if let Err(e) = sync::pull(*rebase, config) {
+                            cliclack::note("T_T", format!("Unable to pull changes: {e}")).unwrap();
                         }
                     }
                 }